@@ -0,0 +1,227 @@
+//! Benchmarks for every registered generator and solver, run against
+//! big synthetic inputs.
+//!
+//! The inputs come from a seeded random number generator, so they're
+//! the same every run and the numbers can be compared between runs.
+
+use aoc2022::day3::hashset;
+use aoc2022::{find, solvers, Solver};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::fmt::Write;
+use std::hint::black_box;
+
+/// A little xorshift generator. Good enough for making up puzzle input
+/// and it means the benches don't need another dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `low..=high`.
+    fn between(&mut self, low: usize, high: usize) -> usize {
+        low + (self.next() % (high - low + 1) as u64) as usize
+    }
+
+    fn pick(&mut self, choices: &[u8]) -> char {
+        choices[self.between(0, choices.len() - 1)] as char
+    }
+}
+
+const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// 100k elves carrying up to 20 items each.
+fn day1_input(rng: &mut Rng) -> String {
+    let mut input = String::new();
+    for _ in 0..100_000 {
+        for _ in 0..rng.between(1, 20) {
+            writeln!(input, "{}", rng.between(1, 60_000)).unwrap();
+        }
+        input.push('\n');
+    }
+    input
+}
+
+/// A million rounds of rock paper scissors.
+fn day2_input(rng: &mut Rng) -> String {
+    let mut input = String::new();
+    for _ in 0..1_000_000 {
+        writeln!(input, "{} {}", rng.pick(b"ABC"), rng.pick(b"XYZ")).unwrap();
+    }
+    input
+}
+
+/// 100k rucksacks. Each one has an item in both compartments, and each
+/// group of three has a badge in common.
+fn day3_input(rng: &mut Rng) -> String {
+    let mut input = String::new();
+    for _ in 0..100_000 / 3 {
+        let badge = rng.pick(ITEMS);
+        for _ in 0..3 {
+            let half = rng.between(4, 24);
+            let shared = rng.pick(ITEMS);
+            let mut left: String = (0..half - 2).map(|_| rng.pick(ITEMS)).collect();
+            let right: String = (0..half - 1).map(|_| rng.pick(ITEMS)).collect();
+            left.push(badge);
+            writeln!(input, "{}{}{}{}", left, shared, right, shared).unwrap();
+        }
+    }
+    input
+}
+
+/// 100k pairs of assignments.
+fn day4_input(rng: &mut Rng) -> String {
+    let mut input = String::new();
+    for _ in 0..100_000 {
+        let mut range = || {
+            let start = rng.between(1, 99);
+            (start, rng.between(start, 99))
+        };
+        let ((a, b), (c, d)) = (range(), range());
+        writeln!(input, "{}-{},{}-{}", a, b, c, d).unwrap();
+    }
+    input
+}
+
+/// Nine stacks of 50 crates, and 100k moves that always leave at least
+/// one crate behind, so every stack still has a top at the end.
+fn day5_input(rng: &mut Rng) -> String {
+    let mut heights = [50; 9];
+    let mut input = String::new();
+
+    for _ in 0..50 {
+        let crates: Vec<String> = (0..9)
+            .map(|_| format!("[{}]", rng.pick(&ITEMS[26..])))
+            .collect();
+        writeln!(input, "{}", crates.join(" ")).unwrap();
+    }
+    input.push_str(" 1   2   3   4   5   6   7   8   9 \n\n");
+
+    for _ in 0..100_000 {
+        let from = loop {
+            let from = rng.between(0, 8);
+            if heights[from] > 1 {
+                break from;
+            }
+        };
+        let to = (from + rng.between(1, 8)) % 9;
+        let n = rng.between(1, (heights[from] - 1).min(10));
+        heights[from] -= n;
+        heights[to] += n;
+        writeln!(input, "move {} from {} to {}", n, from + 1, to + 1).unwrap();
+    }
+    input
+}
+
+/// A megabyte of datastream that only uses three letters, so neither
+/// marker shows up until the very end.
+fn day6_input(rng: &mut Rng) -> String {
+    let mut input: String = (0..1 << 20).map(|_| rng.pick(b"abc")).collect();
+    input.push_str("abcdefghijklmnopqrstuvwxyz");
+    input
+}
+
+/// A filesystem about six directories deep with a few thousand
+/// directories in it.
+fn day7_input(rng: &mut Rng) -> String {
+    fn explore(rng: &mut Rng, input: &mut String, depth: usize) {
+        input.push_str("$ ls\n");
+        let dirs = if depth < 6 { rng.between(1, 4) } else { 0 };
+        for d in 0..dirs {
+            writeln!(input, "dir d{}", d).unwrap();
+        }
+        for f in 0..rng.between(0, 8) {
+            writeln!(input, "{} f{}.txt", rng.between(1, 300_000), f).unwrap();
+        }
+        for d in 0..dirs {
+            writeln!(input, "$ cd d{}", d).unwrap();
+            explore(rng, input, depth + 1);
+            input.push_str("$ cd ..\n");
+        }
+    }
+
+    let mut input = String::from("$ cd /\n");
+    explore(rng, &mut input, 0);
+    input
+}
+
+fn input_for(day: u8) -> String {
+    let mut rng = Rng::new(0x2022_1201 + u64::from(day));
+    match day {
+        1 => day1_input(&mut rng),
+        2 => day2_input(&mut rng),
+        3 => day3_input(&mut rng),
+        4 => day4_input(&mut rng),
+        5 => day5_input(&mut rng),
+        6 => day6_input(&mut rng),
+        7 => day7_input(&mut rng),
+        _ => panic!("no synthetic input for day {}", day),
+    }
+}
+
+fn bench_solver(c: &mut Criterion, solver: &dyn Solver, input: &str) {
+    let mut group = c.benchmark_group(format!(
+        "day{}/part{}/{}",
+        solver.day(),
+        solver.part(),
+        solver.name()
+    ));
+    group.sample_size(20);
+
+    group.bench_function("generator", |b| {
+        b.iter(|| solver.generate(black_box(input)).unwrap())
+    });
+
+    let generated = solver.generate(input).unwrap();
+    group.bench_function("solver", |b| b.iter(|| generated.solve()));
+
+    group.finish();
+}
+
+fn bench_solutions(c: &mut Criterion) {
+    let mut inputs = Vec::new();
+    for solver in solvers() {
+        if !inputs.iter().any(|(day, _)| *day == solver.day()) {
+            inputs.push((solver.day(), input_for(solver.day())));
+        }
+        let (_, input) = inputs.iter().find(|(day, _)| *day == solver.day()).unwrap();
+        bench_solver(c, solver, input);
+    }
+}
+
+/// Finding shared items with `ItemSet` against the `HashSet` way we
+/// used to do it. That all happens in the generators, so that's what
+/// we time.
+fn bench_item_sets(c: &mut Criterion) {
+    let input = input_for(3);
+    let mut group = c.benchmark_group("day3/item sets");
+    group.sample_size(20);
+
+    let compartments = find(3, 1, None).unwrap();
+    let groups = find(3, 2, None).unwrap();
+    group.bench_function("ItemSet", |b| {
+        b.iter(|| {
+            black_box(compartments.generate(&input).unwrap());
+            black_box(groups.generate(&input).unwrap());
+        })
+    });
+    group.bench_function("HashSet", |b| {
+        b.iter(|| {
+            black_box(hashset::compartment_priorities(&input).unwrap());
+            black_box(hashset::group_priorities(&input).unwrap());
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_solutions, bench_item_sets);
+criterion_main!(benches);
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        for solver in aoc2022::solvers().filter(|solver| solver.day() == 1) {
+            let _ = solver.solve(input);
+        }
+    }
+});
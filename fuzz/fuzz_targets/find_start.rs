@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Day 6 doesn't have a generator, so solving it goes straight to
+// `find_start`.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        for solver in aoc2022::solvers().filter(|solver| solver.day() == 6) {
+            let _ = solver.solve(input);
+        }
+    }
+});
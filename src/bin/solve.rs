@@ -0,0 +1,269 @@
+//! Run a single day and part without going through `cargo aoc`.
+//!
+//! ```text
+//! solve <day> <part> [input] [--time]
+//! solve verify [answers] [input-dir] [--strict]
+//! ```
+//!
+//! The input is read from the given file, or from stdin when it's
+//! left off or is `-`. The answer goes to stdout; with `--time`, how
+//! long the generator and the solver took goes to stderr. If the input
+//! doesn't parse or has no answer, the error goes to stderr and the
+//! exit status is 1.
+//!
+//! `verify` runs every solution on its input from `input-dir`
+//! (`input/2022` by default, where `cargo aoc` puts them) and checks
+//! the answers against the answers file (`answers.toml` by default).
+//! The exit status is 1 if any answer is wrong or any solver fails.
+//! Days without an input or an answer are only reported, unless
+//! `--strict` is given, in which case they count as failures too.
+
+use aoc2022::verify::{verify_all, Answers, Report};
+use std::io::Read;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
+use std::{env, fs, io};
+
+const USAGE: &str = "usage: solve <day> <part> [input] [--time]
+       solve verify [answers] [input-dir] [--strict]";
+
+struct Args {
+    day: u8,
+    part: u8,
+    input: Option<String>,
+    time: bool,
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let (flags, positional): (Vec<String>, Vec<String>) =
+        args.partition(|arg| arg.starts_with("--"));
+
+    let time = match &flags[..] {
+        [] => false,
+        [flag] if flag == "--time" => true,
+        _ => return Err(format!("unknown option in {:?}", flags)),
+    };
+
+    let number = |arg: &str, what: &str| {
+        arg.parse::<u8>()
+            .map_err(|_| format!("{} should be a number, not `{}`", what, arg))
+    };
+
+    match &positional[..] {
+        [day, part] => Ok(Args {
+            day: number(day, "day")?,
+            part: number(part, "part")?,
+            input: None,
+            time,
+        }),
+        [day, part, input] => Ok(Args {
+            day: number(day, "day")?,
+            part: number(part, "part")?,
+            input: Some(input.clone()).filter(|input| input != "-"),
+            time,
+        }),
+        _ => Err(String::from("wrong number of arguments")),
+    }
+}
+
+fn read_input(path: Option<&str>) -> io::Result<String> {
+    match path {
+        Some(path) => fs::read_to_string(path),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+    }
+}
+
+struct VerifyArgs {
+    answers: String,
+    input_dir: String,
+    strict: bool,
+}
+
+fn parse_verify_args(args: impl Iterator<Item = String>) -> Result<VerifyArgs, String> {
+    let (flags, positional): (Vec<String>, Vec<String>) =
+        args.partition(|arg| arg.starts_with("--"));
+
+    let strict = match &flags[..] {
+        [] => false,
+        [flag] if flag == "--strict" => true,
+        _ => return Err(format!("unknown option in {:?}", flags)),
+    };
+
+    let (answers, input_dir) = match &positional[..] {
+        [] => ("answers.toml", "input/2022"),
+        [answers] => (answers.as_str(), "input/2022"),
+        [answers, input_dir] => (answers.as_str(), input_dir.as_str()),
+        _ => return Err(String::from("wrong number of arguments")),
+    };
+    Ok(VerifyArgs {
+        answers: answers.to_string(),
+        input_dir: input_dir.to_string(),
+        strict,
+    })
+}
+
+fn run_verify(args: impl Iterator<Item = String>) -> ExitCode {
+    let args = match parse_verify_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let answers_path = &args.answers;
+
+    let answers = match fs::read_to_string(answers_path) {
+        Ok(answers) => answers,
+        Err(e) => {
+            eprintln!("couldn't read {}: {}", answers_path, e);
+            return ExitCode::from(2);
+        }
+    };
+    let answers = match Answers::parse(&answers) {
+        Ok(answers) => answers,
+        Err(e) => {
+            eprintln!("{}: {}", answers_path, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let reports = verify_all(&answers, |day| {
+        fs::read_to_string(Path::new(&args.input_dir).join(format!("day{}.txt", day))).ok()
+    });
+    for report in &reports {
+        println!("{}", report);
+    }
+
+    let failed = |report: &Report| {
+        if args.strict {
+            !report.outcome.is_pass()
+        } else {
+            report.outcome.is_failure()
+        }
+    };
+    if reports.iter().any(failed) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("verify") {
+        return run_verify(args.into_iter().skip(1));
+    }
+
+    let args = match parse_args(args.into_iter()) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let solver = match aoc2022::find(args.day, args.part, None) {
+        Some(solver) => solver,
+        None => {
+            eprintln!("no solution for day {} part {}", args.day, args.part);
+            return ExitCode::from(2);
+        }
+    };
+
+    let input = match read_input(args.input.as_deref()) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("couldn't read input: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let start = Instant::now();
+    let generated = match solver.generate(&input) {
+        Ok(generated) => generated,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let generated_at = Instant::now();
+    let answer = match generated.solve() {
+        Ok(answer) => answer,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let solved_at = Instant::now();
+
+    println!("{}", answer);
+    if args.time {
+        eprintln!("generator: {:?}", generated_at - start);
+        eprintln!("solver: {:?}", solved_at - generated_at);
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&["5", "2", "input.txt", "--time"]).unwrap();
+        assert_eq!((5, 2), (parsed.day, parsed.part));
+        assert_eq!(Some("input.txt"), parsed.input.as_deref());
+        assert!(parsed.time);
+
+        let parsed = args(&["--time", "1", "1", "-"]).unwrap();
+        assert_eq!(None, parsed.input);
+        assert!(parsed.time);
+
+        assert!(!args(&["1", "1"]).unwrap().time);
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(args(&["1"]).is_err());
+        assert!(args(&["one", "1"]).is_err());
+        assert!(args(&["1", "1", "--verbose"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_verify_args() {
+        let parse = |args: &[&str]| parse_verify_args(args.iter().map(|arg| arg.to_string()));
+
+        let parsed = parse(&[]).unwrap();
+        assert_eq!(
+            ("answers.toml", "input/2022", false),
+            (
+                parsed.answers.as_str(),
+                parsed.input_dir.as_str(),
+                parsed.strict
+            )
+        );
+
+        let parsed = parse(&["--strict", "mine.toml", "inputs"]).unwrap();
+        assert_eq!(
+            ("mine.toml", "inputs", true),
+            (
+                parsed.answers.as_str(),
+                parsed.input_dir.as_str(),
+                parsed.strict
+            )
+        );
+
+        assert!(parse(&["a", "b", "c"]).is_err());
+        assert!(parse(&["--time"]).is_err());
+    }
+}
@@ -1,16 +1,28 @@
+use crate::error::{parse_number, ParseError, ParseErrorKind};
+use crate::registry::{Solution, Solver};
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::io::{self, BufRead};
 
 #[aoc_generator(day1)]
-pub fn elf_load_generator(input: &str) -> Vec<i32> {
-    input.lines().fold(vec![0], |mut acc, l| {
-        if l.is_empty() {
-            acc.push(0);
-        } else {
-            let len = acc.len();
-            acc[len - 1] += l.parse::<i32>().unwrap();
-        }
-        acc
-    })
+pub fn elf_load_generator(input: &str) -> Result<Vec<i32>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .try_fold(vec![0i32], |mut acc, (i, l)| {
+            if l.is_empty() {
+                acc.push(0);
+            } else {
+                let len = acc.len();
+                let calories = parse_number(1, l, l).map_err(|e| e.on_line(i + 1))?;
+                acc[len - 1] = acc[len - 1].checked_add(calories).ok_or_else(|| {
+                    ParseError::new(1, ParseErrorKind::Overflow, l, l).on_line(i + 1)
+                })?;
+            }
+            Ok(acc)
+        })
 }
 
 #[aoc(day1, part1)]
@@ -20,15 +32,322 @@ fn day1part1(loads: &[i32]) -> i32 {
 
 #[aoc(day1, part2)]
 fn day1part2(loads: &[i32]) -> i32 {
-    let loads = &mut loads.to_owned();
-    loads.sort_unstable();
-    loads.reverse();
-    loads.iter().take(3).sum()
+    let mut top = TopK::new(3);
+    loads.iter().for_each(|&load| top.push(load));
+    top.into_sorted_vec().iter().sum()
+}
+
+/// Keeps the `k` greatest of everything pushed into it, without
+/// holding on to anything else.
+///
+/// It's a min-heap of at most `k` items, so the smallest of the ones
+/// we're keeping is always on top, ready to be bumped off by anything
+/// bigger.
+pub struct TopK<T> {
+    k: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(item));
+        } else if let Some(mut smallest) = self.heap.peek_mut() {
+            if item > smallest.0 {
+                smallest.0 = item;
+            }
+        }
+    }
+
+    /// Everything we kept, biggest first.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|r| r.0)
+            .collect()
+    }
+}
+
+/// How many calories an elf is carrying, and which elf it is
+/// (counting from 0 in input order).
+///
+/// The heavier load is the greater one. For the same number of
+/// calories, the elf who came first in the input wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElfLoad {
+    pub elf: usize,
+    pub total: u64,
+}
+
+impl Ord for ElfLoad {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.total
+            .cmp(&other.total)
+            .then_with(|| other.elf.cmp(&self.elf))
+    }
+}
+
+impl PartialOrd for ElfLoad {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Find the `k` elves carrying the most calories, reading the
+/// inventory a line at a time so it never has to fit in memory.
+///
+/// Input that doesn't parse comes back as an `InvalidData` error
+/// wrapping the [`ParseError`].
+pub fn top_loads(mut reader: impl BufRead, k: usize) -> io::Result<Vec<ElfLoad>> {
+    let mut top = TopK::new(k);
+    let mut current = ElfLoad { elf: 0, total: 0 };
+    let mut buf = String::new();
+    let mut line_number = 0;
+    // Starting out as if we'd just read a blank line means empty input
+    // has no elves, and a blank line at the very end doesn't count as
+    // another elf.
+    let mut last_was_blank = true;
+
+    while reader.read_line(&mut buf)? > 0 {
+        line_number += 1;
+        let line = buf.trim_end_matches('\n').trim_end_matches('\r');
+        last_was_blank = line.is_empty();
+        if line.is_empty() {
+            top.push(current);
+            current = ElfLoad {
+                elf: current.elf + 1,
+                total: 0,
+            };
+        } else {
+            let at_line =
+                |e: ParseError| io::Error::new(io::ErrorKind::InvalidData, e.on_line(line_number));
+            let calories: u64 = parse_number(1, line, line).map_err(at_line)?;
+            current.total = current
+                .total
+                .checked_add(calories)
+                .ok_or_else(|| at_line(ParseError::new(1, ParseErrorKind::Overflow, line, line)))?;
+        }
+        buf.clear();
+    }
+    if !last_was_blank {
+        top.push(current);
+    }
+
+    Ok(top.into_sorted_vec())
+}
+
+/// One elf's pack, with every food item still in it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Elf {
+    pub items: Vec<u64>,
+}
+
+impl Elf {
+    pub fn total(&self) -> u64 {
+        self.items.iter().sum()
+    }
+}
+
+/// Like [`elf_load_generator`], but keeping each item instead of
+/// adding them up. Two blank lines in a row mean an elf with an empty
+/// pack. As with [`top_loads`], a blank line at the very end doesn't
+/// start another elf, and empty input has no elves at all.
+pub fn parse_elves(input: &str) -> Result<Vec<Elf>, ParseError> {
+    let mut elves = Vec::new();
+    let mut current = Elf::default();
+    // Kept as we go so we can check for overflow without adding up the
+    // whole pack again for every item.
+    let mut total: u64 = 0;
+    let mut last_was_blank = true;
+
+    for (i, l) in input.lines().enumerate() {
+        last_was_blank = l.is_empty();
+        if l.is_empty() {
+            elves.push(std::mem::take(&mut current));
+            total = 0;
+        } else {
+            let calories = parse_number(1, l, l).map_err(|e| e.on_line(i + 1))?;
+            total = total
+                .checked_add(calories)
+                .ok_or_else(|| ParseError::new(1, ParseErrorKind::Overflow, l, l).on_line(i + 1))?;
+            current.items.push(calories);
+        }
+    }
+    if !last_was_blank {
+        elves.push(current);
+    }
+
+    Ok(elves)
+}
+
+/// Statistics about how many calories the elves are carrying.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InventoryStats {
+    /// Every elf's total, smallest first.
+    totals: Vec<u64>,
+    empty_packs: Vec<usize>,
+}
+
+impl InventoryStats {
+    pub fn new(elves: &[Elf]) -> Self {
+        let mut totals: Vec<u64> = elves.iter().map(Elf::total).collect();
+        totals.sort_unstable();
+        Self {
+            totals,
+            empty_packs: elves
+                .iter()
+                .enumerate()
+                .filter(|(_, elf)| elf.items.is_empty())
+                .map(|(i, _)| i)
+                .collect(),
+        }
+    }
+
+    pub fn elves(&self) -> usize {
+        self.totals.len()
+    }
+
+    /// Every elf's total added up. Each of those fits in a `u64`, but
+    /// all of them together might not.
+    pub fn total(&self) -> u128 {
+        self.totals.iter().map(|&total| u128::from(total)).sum()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        match self.elves() {
+            0 => None,
+            n => Some(self.total() as f64 / n as f64),
+        }
+    }
+
+    /// The middle total, or halfway between the two middle ones if
+    /// there's an even number of elves.
+    pub fn median(&self) -> Option<f64> {
+        let n = self.elves();
+        match n {
+            0 => None,
+            _ if n % 2 == 1 => Some(self.totals[n / 2] as f64),
+            _ => {
+                let middle = u128::from(self.totals[n / 2 - 1]) + u128::from(self.totals[n / 2]);
+                Some(middle as f64 / 2.0)
+            }
+        }
+    }
+
+    /// The smallest total that at least `p` percent of the elves are
+    /// carrying no more than (the nearest-rank percentile).
+    pub fn percentile(&self, p: f64) -> Option<u64> {
+        let n = self.elves();
+        if n == 0 || !(0.0..=100.0).contains(&p) {
+            return None;
+        }
+        let rank = (p / 100.0 * n as f64).ceil() as usize;
+        Some(self.totals[rank.clamp(1, n) - 1])
+    }
+
+    /// The most buckets [`InventoryStats::histogram`] will make.
+    pub const MAX_BUCKETS: usize = 1 << 16;
+
+    /// How many elves' totals fall into each bucket of `width`
+    /// calories, from 0 up to the biggest total. Buckets are given by
+    /// where they start. `None` if that would take more than
+    /// [`InventoryStats::MAX_BUCKETS`] buckets.
+    pub fn histogram(&self, width: u64) -> Option<Vec<(u64, usize)>> {
+        let width = width.max(1);
+        let buckets = match self.totals.last() {
+            Some(max) => (max / width)
+                .checked_add(1)
+                .and_then(|buckets| usize::try_from(buckets).ok())
+                .filter(|&buckets| buckets <= Self::MAX_BUCKETS)?,
+            None => 0,
+        };
+        let mut counts = vec![0; buckets];
+        for total in &self.totals {
+            counts[(total / width) as usize] += 1;
+        }
+        Some(
+            counts
+                .into_iter()
+                .enumerate()
+                .map(|(i, count)| (i as u64 * width, count))
+                .collect(),
+        )
+    }
+
+    /// Which elves (counting from 0) have nothing in their packs.
+    pub fn empty_packs(&self) -> &[usize] {
+        &self.empty_packs
+    }
 }
 
+impl fmt::Display for InventoryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "elves: {}", self.elves())?;
+        writeln!(f, "empty packs: {:?}", self.empty_packs())?;
+        writeln!(f, "total calories: {}", self.total())?;
+        if let (Some(mean), Some(median)) = (self.mean(), self.median()) {
+            writeln!(f, "mean: {:.1}", mean)?;
+            writeln!(f, "median: {:.1}", median)?;
+        }
+        for p in [10, 25, 75, 90, 99] {
+            if let Some(total) = self.percentile(p as f64) {
+                writeln!(f, "p{}: {}", p, total)?;
+            }
+        }
+
+        let width = match self.totals.last() {
+            Some(&max) if max >= 10 => max / 10,
+            _ => 1,
+        };
+        // That's never more than 11 buckets.
+        let histogram = self.histogram(width).unwrap_or_default();
+        let widest = histogram.iter().map(|(_, count)| *count).max().unwrap_or(0);
+        writeln!(f, "histogram:")?;
+        for (start, count) in histogram {
+            // Scale the bars so the longest one is 40 characters.
+            let bar = (count * 40).div_ceil(widest.max(1));
+            writeln!(
+                f,
+                "{:>10}-{:<10} {:>6} {}",
+                start,
+                start.saturating_add(width - 1),
+                count,
+                "#".repeat(bar)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) static SOLVERS: &[&dyn Solver] = &[
+    &Solution {
+        day: 1,
+        part: 1,
+        name: "day1part1",
+        generator: elf_load_generator,
+        solver: |loads| Ok(day1part1(loads).into()),
+    },
+    &Solution {
+        day: 1,
+        part: 2,
+        name: "day1part2",
+        generator: elf_load_generator,
+        solver: |loads| Ok(day1part2(loads).into()),
+    },
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const INPUT: &str = "1000
 2000
@@ -48,16 +367,219 @@ mod tests {
 
     #[test]
     fn generator_test() {
-        assert_eq!(elf_load_generator(INPUT), vec![3000, 5000, 3040, 11111]);
+        assert_eq!(
+            elf_load_generator(INPUT).unwrap(),
+            vec![3000, 5000, 3040, 11111]
+        );
     }
 
     #[test]
     fn part1_test() {
-        assert_eq!(day1part1(&elf_load_generator(INPUT)), 11111);
+        assert_eq!(day1part1(&elf_load_generator(INPUT).unwrap()), 11111);
     }
 
     #[test]
     fn part2_test() {
-        assert_eq!(day1part2(&elf_load_generator(INPUT)), 11111 + 5000 + 3040);
+        assert_eq!(
+            day1part2(&elf_load_generator(INPUT).unwrap()),
+            11111 + 5000 + 3040
+        );
+    }
+
+    #[test]
+    fn top_k_test() {
+        let mut top = TopK::new(2);
+        [5, 1, 9, 3, 9].into_iter().for_each(|n| top.push(n));
+        assert_eq!(vec![9, 9], top.into_sorted_vec());
+
+        let mut top = TopK::new(0);
+        top.push(1);
+        assert_eq!(Vec::<i32>::new(), top.into_sorted_vec());
+    }
+
+    #[test]
+    fn top_loads_test() {
+        assert_eq!(
+            vec![
+                ElfLoad {
+                    elf: 3,
+                    total: 11111
+                },
+                ElfLoad {
+                    elf: 1,
+                    total: 5000
+                },
+                ElfLoad {
+                    elf: 2,
+                    total: 3040
+                },
+            ],
+            top_loads(INPUT.as_bytes(), 3).unwrap()
+        );
+        assert_eq!(
+            4,
+            top_loads(INPUT.replace('\n', "\r\n").as_bytes(), 10)
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn top_loads_ties_test() {
+        assert_eq!(
+            vec![ElfLoad { elf: 0, total: 5 }, ElfLoad { elf: 2, total: 5 }],
+            top_loads("5\n\n1\n\n5\n\n5".as_bytes(), 2).unwrap()
+        );
+        assert_eq!(2, top_loads("1\n\n2\n\n".as_bytes(), 5).unwrap().len());
+        assert!(top_loads("".as_bytes(), 5).unwrap().is_empty());
+        assert_eq!(1, top_loads("7".as_bytes(), usize::MAX).unwrap().len());
+    }
+
+    #[test]
+    fn top_loads_error_test() {
+        let err = top_loads("1\n\nx\n".as_bytes(), 3).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        let err = err.into_inner().unwrap().downcast::<ParseError>().unwrap();
+        assert_eq!((3, ParseErrorKind::InvalidNumber), (err.line, err.kind));
+
+        let err = top_loads("18446744073709551615\n1".as_bytes(), 3).unwrap_err();
+        let err = err.into_inner().unwrap().downcast::<ParseError>().unwrap();
+        assert_eq!((2, ParseErrorKind::Overflow), (err.line, err.kind));
+    }
+
+    #[test]
+    fn parse_elves_test() {
+        let elves = parse_elves("1\n2\n\n\n3").unwrap();
+        assert_eq!(
+            vec![
+                Elf { items: vec![1, 2] },
+                Elf { items: vec![] },
+                Elf { items: vec![3] }
+            ],
+            elves
+        );
+        assert_eq!(
+            elf_load_generator(INPUT).unwrap(),
+            parse_elves(INPUT)
+                .unwrap()
+                .iter()
+                .map(|elf| elf.total() as i32)
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(vec![Elf { items: vec![1] }], parse_elves("1\n\n").unwrap());
+        assert!(parse_elves("").unwrap().is_empty());
+
+        let err = parse_elves("1\n\n-2").unwrap_err();
+        assert_eq!((3, ParseErrorKind::InvalidNumber), (err.line, err.kind));
+    }
+
+    #[test]
+    fn inventory_stats_test() {
+        let stats = InventoryStats::new(&parse_elves(INPUT).unwrap());
+        assert_eq!(4, stats.elves());
+        assert_eq!(22151, stats.total());
+        assert_eq!(Some(5537.75), stats.mean());
+        assert_eq!(Some(4020.0), stats.median());
+        assert_eq!(Some(3000), stats.percentile(0.0));
+        assert_eq!(Some(3000), stats.percentile(25.0));
+        assert_eq!(Some(3040), stats.percentile(50.0));
+        assert_eq!(Some(11111), stats.percentile(90.0));
+        assert_eq!(Some(11111), stats.percentile(100.0));
+        assert_eq!(None, stats.percentile(101.0));
+        assert_eq!(
+            vec![(0, 0), (2500, 2), (5000, 1), (7500, 0), (10000, 1)],
+            stats.histogram(2500).unwrap()
+        );
+        assert!(stats.empty_packs().is_empty());
+    }
+
+    #[test]
+    fn inventory_stats_empty_test() {
+        let stats = InventoryStats::new(&parse_elves("5\n\n\n7").unwrap());
+        assert_eq!(&[1], stats.empty_packs());
+        assert_eq!(Some(5.0), stats.median());
+
+        let stats = InventoryStats::new(&[]);
+        assert_eq!(None, stats.mean());
+        assert_eq!(None, stats.median());
+        assert_eq!(None, stats.percentile(50.0));
+        assert_eq!(Some(vec![]), stats.histogram(10));
+        assert!(InventoryStats::new(&parse_elves("").unwrap())
+            .empty_packs()
+            .is_empty());
+    }
+
+    #[test]
+    fn inventory_stats_overflow_test() {
+        let half = 1u64 << 63;
+        let stats = InventoryStats::new(&parse_elves(&format!("{}\n\n{}", half, half)).unwrap());
+        assert_eq!(1 << 64, stats.total());
+        assert_eq!(Some(half as f64), stats.median());
+        assert!(stats.to_string().contains("elves: 2\n"));
+
+        let stats = InventoryStats::new(&parse_elves(&u64::MAX.to_string()).unwrap());
+        assert!(stats.to_string().contains(&format!("-{} ", u64::MAX)));
+        assert_eq!(None, stats.histogram(1));
+        assert_eq!(None, stats.histogram(1 << 40));
+        assert_eq!(Some(vec![(0, 0), (1 << 63, 1)]), stats.histogram(1 << 63));
+    }
+
+    #[test]
+    fn inventory_report_test() {
+        let report = InventoryStats::new(&parse_elves(INPUT).unwrap()).to_string();
+        assert!(report.starts_with("elves: 4\nempty packs: []\ntotal calories: 22151\n"));
+        assert!(report.contains("median: 4020.0\n"));
+        assert!(report.contains("p90: 11111\n"));
+    }
+
+    #[test]
+    fn generator_overflow_test() {
+        let err = elf_load_generator("1\n2147483647").unwrap_err();
+        assert_eq!((2, ParseErrorKind::Overflow), (err.line, err.kind));
+    }
+
+    #[test]
+    fn generator_error_test() {
+        let err = elf_load_generator("1000\n\n20x0\n").unwrap_err();
+        assert_eq!((1, 3, 1), (err.day, err.line, err.column));
+        assert_eq!("20x0", err.text);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_generator_sums(elves in prop::collection::vec(prop::collection::vec(0..100_000i32, 1..10), 1..50)) {
+            let input = elves
+                .iter()
+                .map(|items| items.iter().map(i32::to_string).collect::<Vec<_>>().join("\n"))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            let loads = elf_load_generator(&input).unwrap();
+
+            prop_assert_eq!(
+                elves.iter().map(|items| items.iter().sum()).collect::<Vec<i32>>(),
+                loads.clone()
+            );
+            prop_assert_eq!(
+                elves.iter().flatten().sum::<i32>(),
+                loads.iter().sum::<i32>()
+            );
+        }
+
+        #[test]
+        fn prop_top_loads_matches_sorting(
+            loads in prop::collection::vec(0..1_000u64, 1..50),
+            k in 0..10usize
+        ) {
+            let input = loads.iter().map(u64::to_string).collect::<Vec<_>>().join("\n\n");
+            let mut sorted: Vec<ElfLoad> = loads
+                .iter()
+                .enumerate()
+                .map(|(elf, &total)| ElfLoad { elf, total })
+                .collect();
+            sorted.sort_by(|a, b| b.cmp(a));
+            sorted.truncate(k);
+            prop_assert_eq!(sorted, top_loads(input.as_bytes(), k).unwrap());
+        }
     }
 }
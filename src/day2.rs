@@ -1,7 +1,338 @@
+use crate::error::{parse_lines, ParseError, ParseErrorKind};
+use crate::registry::{Solution, Solver};
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Games like rock paper scissors, generalized to any odd number of
+/// shapes.
+///
+/// Shapes are numbered from 0. Every pair of different shapes has a
+/// winner, and every shape beats exactly half of the others, so no
+/// shape is better than any other.
+pub mod game {
+    use std::collections::BTreeMap;
+    use std::fmt;
+
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+    pub enum Outcome {
+        Win,
+        Loss,
+        Tie,
+    }
+
+    /// How many points each outcome is worth.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct OutcomeScores {
+        pub win: i32,
+        pub tie: i32,
+        pub loss: i32,
+    }
+
+    impl OutcomeScores {
+        pub fn score(&self, outcome: Outcome) -> i32 {
+            match outcome {
+                Outcome::Win => self.win,
+                Outcome::Tie => self.tie,
+                Outcome::Loss => self.loss,
+            }
+        }
+    }
+
+    impl Default for OutcomeScores {
+        /// The scores from the elves' tournament.
+        fn default() -> Self {
+            Self {
+                win: 6,
+                tie: 3,
+                loss: 0,
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub enum GameError {
+        /// With an even number of shapes, some shape has to beat more
+        /// of the others than it loses to.
+        EvenShapes(usize),
+        /// A shape that isn't one of the game's shapes.
+        NoSuchShape(usize),
+        /// Two shapes that either both beat each other or that we don't
+        /// know the winner of.
+        Undecided(usize, usize),
+        /// A shape that doesn't beat exactly half of the others.
+        Unbalanced(usize),
+        /// Shape scores that don't line up with the shapes.
+        ShapeScores { shapes: usize, scores: usize },
+        /// Limits on how many times to play each shape that don't line
+        /// up with the shapes.
+        Limits { shapes: usize, limits: usize },
+    }
+
+    impl fmt::Display for GameError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                GameError::EvenShapes(n) => write!(f, "{} shapes can't be balanced", n),
+                GameError::NoSuchShape(a) => write!(f, "there's no shape {}", a),
+                GameError::Undecided(a, b) => {
+                    write!(f, "shapes {} and {} need exactly one winner", a, b)
+                }
+                GameError::Unbalanced(a) => {
+                    write!(f, "shape {} doesn't beat exactly half the others", a)
+                }
+                GameError::ShapeScores { shapes, scores } => {
+                    write!(f, "{} scores for {} shapes", scores, shapes)
+                }
+                GameError::Limits { shapes, limits } => {
+                    write!(f, "{} limits for {} shapes", limits, shapes)
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for GameError {}
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub struct Game {
+        /// `beats[a][b]` is whether shape `a` beats shape `b`.
+        beats: Vec<Vec<bool>>,
+        shape_scores: Vec<i32>,
+        outcome_scores: OutcomeScores,
+    }
+
+    impl Game {
+        /// A game where each shape is beaten by the shape after it, the
+        /// one three after it, five after, and so on, wrapping around.
+        /// With three shapes that's rock, paper, scissors. Shapes score
+        /// 1, 2, 3, ... in order.
+        pub fn cyclic(shapes: usize) -> Result<Self, GameError> {
+            let pairs: Vec<(usize, usize)> = (0..shapes)
+                .flat_map(|a| (1..shapes).step_by(2).map(move |d| ((a + d) % shapes, a)))
+                .collect();
+            Self::from_beats(shapes, &pairs)
+        }
+
+        /// A game with `shapes` shapes, where `(a, b)` in `beats` means
+        /// shape `a` beats shape `b`. Shapes score 1, 2, 3, ... in order.
+        pub fn from_beats(shapes: usize, beats: &[(usize, usize)]) -> Result<Self, GameError> {
+            if shapes.is_multiple_of(2) {
+                return Err(GameError::EvenShapes(shapes));
+            }
+            let mut table = vec![vec![false; shapes]; shapes];
+            for &(a, b) in beats {
+                if a >= shapes || b >= shapes {
+                    return Err(GameError::NoSuchShape(a.max(b)));
+                }
+                if a == b {
+                    return Err(GameError::Undecided(a, b));
+                }
+                table[a][b] = true;
+            }
+            for (a, row) in table.iter().enumerate() {
+                if let Some(b) = (a + 1..shapes).find(|&b| row[b] == table[b][a]) {
+                    return Err(GameError::Undecided(a, b));
+                }
+                if row.iter().filter(|&&beats| beats).count() != shapes / 2 {
+                    return Err(GameError::Unbalanced(a));
+                }
+            }
+            Ok(Self {
+                beats: table,
+                shape_scores: (1..=shapes as i32).collect(),
+                outcome_scores: OutcomeScores::default(),
+            })
+        }
+
+        pub fn with_shape_scores(self, shape_scores: Vec<i32>) -> Result<Self, GameError> {
+            if shape_scores.len() != self.shapes() {
+                return Err(GameError::ShapeScores {
+                    shapes: self.shapes(),
+                    scores: shape_scores.len(),
+                });
+            }
+            Ok(Self {
+                shape_scores,
+                ..self
+            })
+        }
+
+        pub fn with_outcome_scores(self, outcome_scores: OutcomeScores) -> Self {
+            Self {
+                outcome_scores,
+                ..self
+            }
+        }
+
+        pub fn shapes(&self) -> usize {
+            self.beats.len()
+        }
+
+        pub fn beats(&self, a: usize, b: usize) -> bool {
+            self.beats[a][b]
+        }
+
+        /// How things turn out for whoever plays `me`.
+        pub fn outcome(&self, me: usize, opponent: usize) -> Outcome {
+            if me == opponent {
+                Outcome::Tie
+            } else if self.beats(me, opponent) {
+                Outcome::Win
+            } else {
+                Outcome::Loss
+            }
+        }
+
+        pub fn shape_score(&self, shape: usize) -> i32 {
+            self.shape_scores[shape]
+        }
+
+        pub fn outcome_score(&self, outcome: Outcome) -> i32 {
+            self.outcome_scores.score(outcome)
+        }
+
+        /// The score for whoever plays `me`: what the shape is worth
+        /// plus what the outcome is worth.
+        pub fn score(&self, me: usize, opponent: usize) -> i32 {
+            self.shape_score(me) + self.outcome_score(self.outcome(me, opponent))
+        }
+
+        /// Every shape that gets `outcome` against `opponent`, lowest
+        /// first.
+        pub fn shapes_for(&self, opponent: usize, outcome: Outcome) -> Vec<usize> {
+            (0..self.shapes())
+                .filter(|&me| self.outcome(me, opponent) == outcome)
+                .collect()
+        }
+
+        /// The highest scoring plays against a known sequence of
+        /// opponent plays, or `None` if there's no way to play every
+        /// round within `rules`. It's an error if the opponent plays a
+        /// shape that isn't in the game, or if `rules` doesn't have a
+        /// limit for every shape.
+        ///
+        /// We go round by round, keeping the best score for each state
+        /// we could be in: the shape we just played and, if the shapes
+        /// are limited, how many times we've played each of them. With
+        /// limits the number of states can grow with the product of
+        /// the limits, so this is only quick when they're small or
+        /// there aren't any.
+        pub fn best_plan(
+            &self,
+            opponent: &[usize],
+            rules: &Rules,
+        ) -> Result<Option<Plan>, GameError> {
+            if let Some(limits) = &rules.max_uses {
+                if limits.len() != self.shapes() {
+                    return Err(GameError::Limits {
+                        shapes: self.shapes(),
+                        limits: limits.len(),
+                    });
+                }
+            }
+            if let Some(&shape) = opponent.iter().find(|&&shape| shape >= self.shapes()) {
+                return Err(GameError::NoSuchShape(shape));
+            }
+
+            #[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
+            struct State {
+                last: Option<usize>,
+                uses: Vec<usize>,
+            }
+
+            let start = State {
+                last: None,
+                uses: vec![0; rules.max_uses.as_ref().map_or(0, Vec::len)],
+            };
+            // For each round, every state we can get to and how we got
+            // there: the best score so far, the state we came from and
+            // what we played.
+            let mut rounds: Vec<BTreeMap<State, (i32, State, usize)>> = Vec::new();
+            let mut best = BTreeMap::from([(start.clone(), 0)]);
+
+            for &theirs in opponent {
+                let mut next: BTreeMap<State, (i32, State, usize)> = BTreeMap::new();
+                for (state, &score) in &best {
+                    for me in 0..self.shapes() {
+                        if rules.no_repeats && state.last == Some(me) {
+                            continue;
+                        }
+                        let mut uses = state.uses.clone();
+                        if let Some(limits) = &rules.max_uses {
+                            if uses[me] == limits[me] {
+                                continue;
+                            }
+                            uses[me] += 1;
+                        }
+                        let score = score + self.score(me, theirs);
+                        let to = State {
+                            last: Some(me),
+                            uses,
+                        };
+                        if next.get(&to).is_none_or(|&(best, _, _)| score > best) {
+                            next.insert(to, (score, state.clone(), me));
+                        }
+                    }
+                }
+                best = next
+                    .iter()
+                    .map(|(state, &(score, _, _))| (state.clone(), score))
+                    .collect();
+                rounds.push(next);
+            }
+
+            let Some((mut state, score)) = best.into_iter().rev().max_by_key(|&(_, score)| score)
+            else {
+                return Ok(None);
+            };
+            let mut plays = Vec::with_capacity(opponent.len());
+            for round in rounds.iter().rev() {
+                let (_, from, me) = &round[&state];
+                plays.push(*me);
+                state = from.clone();
+            }
+            plays.reverse();
+            Ok(Some(Plan { score, plays }))
+        }
+    }
+
+    /// Limits on what we're allowed to play in [`Game::best_plan`].
+    #[derive(Debug, Default, PartialEq, Eq, Clone)]
+    pub struct Rules {
+        /// How many times each shape can be played, if there's a limit.
+        pub max_uses: Option<Vec<usize>>,
+        /// Whether we have to switch shapes every round.
+        pub no_repeats: bool,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub struct Plan<S = usize> {
+        pub score: i32,
+        pub plays: Vec<S>,
+    }
+
+    impl<S> Plan<S> {
+        pub fn map<T>(self, f: impl FnMut(S) -> T) -> Plan<T> {
+            Plan {
+                score: self.score,
+                plays: self.plays.into_iter().map(f).collect(),
+            }
+        }
+    }
+}
 
 #[allow(non_snake_case)]
-mod RPS {
+pub mod RPS {
+    use super::game::{Game, GameError, Outcome, Plan, Rules};
+    use crate::error::{ParseError, ParseErrorKind};
+    use std::str::FromStr;
+    use std::sync::OnceLock;
+
+    /// Rock, paper, scissors as shapes 0, 1 and 2 of a cyclic game.
+    pub fn game() -> &'static Game {
+        static GAME: OnceLock<Game> = OnceLock::new();
+        GAME.get_or_init(|| Game::cyclic(3).unwrap())
+    }
+
     #[derive(Debug, PartialEq, Eq, Clone, Copy)]
     pub enum Play {
         Rock,
@@ -10,27 +341,42 @@ mod RPS {
     }
 
     impl Play {
+        pub const ALL: [Play; 3] = [Play::Rock, Play::Paper, Play::Scissors];
+
+        pub fn shape(self) -> usize {
+            self as usize
+        }
+
+        pub fn from_shape(shape: usize) -> Self {
+            Self::ALL[shape]
+        }
+
         pub fn score(self) -> i32 {
-            match self {
-                Play::Rock => 1,
-                Play::Paper => 2,
-                Play::Scissors => 3,
-            }
+            game().shape_score(self.shape())
         }
     }
 
-    impl From<&str> for Play {
-        fn from(s: &str) -> Self {
+    impl FromStr for Play {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
             match s {
-                "A" | "X" => Play::Rock,
-                "B" | "Y" => Play::Paper,
-                "C" | "Z" => Play::Scissors,
-                _ => panic!(),
+                "A" | "X" => Ok(Play::Rock),
+                "B" | "Y" => Ok(Play::Paper),
+                "C" | "Z" => Ok(Play::Scissors),
+                _ => Err(ParseError::new(
+                    2,
+                    ParseErrorKind::Unexpected {
+                        expected: "one of A, B, C, X, Y or Z",
+                    },
+                    s,
+                    s,
+                )),
             }
         }
     }
 
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
     pub enum Result {
         Win,
         Loss,
@@ -39,26 +385,51 @@ mod RPS {
 
     impl Result {
         pub fn score(&self) -> i32 {
-            match &self {
-                Result::Win => 6,
-                Result::Tie => 3,
-                Result::Loss => 0,
+            game().outcome_score(Outcome::from(*self))
+        }
+    }
+
+    impl From<Result> for Outcome {
+        fn from(res: Result) -> Self {
+            match res {
+                Result::Win => Outcome::Win,
+                Result::Loss => Outcome::Loss,
+                Result::Tie => Outcome::Tie,
             }
         }
     }
 
-    impl From<&str> for Result {
-        fn from(s: &str) -> Self {
+    impl From<Outcome> for Result {
+        fn from(outcome: Outcome) -> Self {
+            match outcome {
+                Outcome::Win => Result::Win,
+                Outcome::Loss => Result::Loss,
+                Outcome::Tie => Result::Tie,
+            }
+        }
+    }
+
+    impl FromStr for Result {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
             match s {
-                "X" => Result::Loss,
-                "Y" => Result::Tie,
-                "Z" => Result::Win,
-                _ => panic!(),
+                "X" => Ok(Result::Loss),
+                "Y" => Ok(Result::Tie),
+                "Z" => Ok(Result::Win),
+                _ => Err(ParseError::new(
+                    2,
+                    ParseErrorKind::Unexpected {
+                        expected: "one of X, Y or Z",
+                    },
+                    s,
+                    s,
+                )),
             }
         }
     }
 
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
     pub struct Round {
         pub opponent: Play,
         pub me: Play,
@@ -66,20 +437,13 @@ mod RPS {
 
     impl Round {
         pub fn round_result(&self) -> Result {
-            if self.opponent == self.me {
-                Result::Tie
-            } else {
-                match (&self.opponent, &self.me) {
-                    (Play::Rock, Play::Scissors)
-                    | (Play::Paper, Play::Rock)
-                    | (Play::Scissors, Play::Paper) => Result::Loss,
-                    _ => Result::Win,
-                }
-            }
+            game()
+                .outcome(self.me.shape(), self.opponent.shape())
+                .into()
         }
 
         pub fn score(&self) -> i32 {
-            self.me.score() + self.round_result().score()
+            game().score(self.me.shape(), self.opponent.shape())
         }
     }
 
@@ -92,61 +456,509 @@ mod RPS {
         }
     }
 
+    #[derive(Debug, PartialEq, Eq)]
     pub struct RoundStrategy {
         pub opponent: Play,
         pub res: Result,
     }
 
+    /// The best we can do against `opponent` if we know everything
+    /// they're going to play. See [`Game::best_plan`].
+    pub fn best_plan(
+        opponent: &[Play],
+        rules: &Rules,
+    ) -> std::result::Result<Option<Plan<Play>>, GameError> {
+        let opponent: Vec<usize> = opponent.iter().map(|play| play.shape()).collect();
+        Ok(game()
+            .best_plan(&opponent, rules)?
+            .map(|plan| plan.map(Play::from_shape)))
+    }
+
     impl RoundStrategy {
         fn my_play(&self) -> Play {
-            match self.res {
-                Result::Tie => self.opponent,
-                Result::Win => match self.opponent {
-                    Play::Rock => Play::Paper,
-                    Play::Paper => Play::Scissors,
-                    Play::Scissors => Play::Rock,
-                },
-                Result::Loss => match self.opponent {
-                    Play::Rock => Play::Scissors,
-                    Play::Paper => Play::Rock,
-                    Play::Scissors => Play::Paper,
-                },
-            }
+            // With three shapes there's only ever one way to get a
+            // particular result.
+            Play::from_shape(game().shapes_for(self.opponent.shape(), self.res.into())[0])
         }
     }
 }
 
-#[aoc_generator(day2, part1)]
-pub fn parse_rps_strategy(input: &str) -> Vec<RPS::Round> {
-    input
-        .lines()
-        .map(|l| {
-            let mut plays = l.split(' ');
-            let opp = plays.next().unwrap();
-            let me = plays.next().unwrap();
-            RPS::Round {
-                opponent: RPS::Play::from(opp),
-                me: RPS::Play::from(me),
+/// Long matches of rock paper scissors between players that can learn
+/// from what their opponents have been doing.
+pub mod tournament {
+    use super::game::Outcome;
+    use super::RPS::{self, Play, Round, RoundStrategy};
+    use std::fmt;
+
+    /// How a player picks what to play.
+    pub trait Strategy {
+        fn name(&self) -> String;
+
+        /// What to play next.
+        fn play(&mut self) -> Play;
+
+        /// Find out how the last round went, once both players have
+        /// played.
+        fn observe(&mut self, _round: &Round) {}
+    }
+
+    /// The play that beats `play`.
+    fn beat(play: Play) -> Play {
+        Play::from_shape(RPS::game().shapes_for(play.shape(), Outcome::Win)[0])
+    }
+
+    /// Always plays the same thing.
+    pub struct Fixed(pub Play);
+
+    impl Strategy for Fixed {
+        fn name(&self) -> String {
+            format!("always {:?}", self.0)
+        }
+
+        fn play(&mut self) -> Play {
+            self.0
+        }
+    }
+
+    /// Plays at random, using a little xorshift generator so the same
+    /// seed always gives the same match.
+    pub struct Random {
+        seed: u64,
+        state: u64,
+    }
+
+    impl Random {
+        pub fn new(seed: u64) -> Self {
+            // Xorshift gets stuck on zero.
+            let state = seed.max(1);
+            Self { seed, state }
+        }
+    }
+
+    impl Strategy for Random {
+        fn name(&self) -> String {
+            format!("random ({})", self.seed)
+        }
+
+        fn play(&mut self) -> Play {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            Play::ALL[(self.state % 3) as usize]
+        }
+    }
+
+    /// Beats whatever the opponent has played the most so far.
+    #[derive(Default)]
+    pub struct FrequencyCounter {
+        seen: [u32; 3],
+    }
+
+    impl Strategy for FrequencyCounter {
+        fn name(&self) -> String {
+            String::from("frequency counter")
+        }
+
+        fn play(&mut self) -> Play {
+            let favorite = (0..3).rev().max_by_key(|&shape| self.seen[shape]).unwrap();
+            beat(Play::from_shape(favorite))
+        }
+
+        fn observe(&mut self, round: &Round) {
+            self.seen[round.opponent.shape()] += 1;
+        }
+    }
+
+    /// Keeps track of what the opponent plays after each of their
+    /// plays, and beats whatever they've most often followed their
+    /// last play with.
+    #[derive(Default)]
+    pub struct Markov {
+        /// `after[a][b]` is how many times they've played `b` right
+        /// after `a`.
+        after: [[u32; 3]; 3],
+        last: Option<Play>,
+    }
+
+    impl Strategy for Markov {
+        fn name(&self) -> String {
+            String::from("markov")
+        }
+
+        fn play(&mut self) -> Play {
+            match self.last {
+                None => Play::Rock,
+                Some(last) => {
+                    let counts = self.after[last.shape()];
+                    let next = (0..3).rev().max_by_key(|&shape| counts[shape]).unwrap();
+                    beat(Play::from_shape(next))
+                }
             }
+        }
+
+        fn observe(&mut self, round: &Round) {
+            if let Some(last) = self.last {
+                self.after[last.shape()][round.opponent.shape()] += 1;
+            }
+            self.last = Some(round.opponent);
+        }
+    }
+
+    /// Plays what the strategy guide says to, in order, starting over
+    /// when it runs out.
+    pub struct Guide {
+        plays: Vec<Play>,
+        next: usize,
+    }
+
+    impl Guide {
+        /// Reading the second column as what to play.
+        pub fn new(rounds: &[Round]) -> Self {
+            Self {
+                plays: rounds.iter().map(|round| round.me).collect(),
+                next: 0,
+            }
+        }
+
+        /// Reading the second column as how the round should end,
+        /// assuming the opponent plays what the guide says they will.
+        pub fn from_strategy(strategy: &[RoundStrategy]) -> Self {
+            Self::new(&strategy.iter().map(Round::from).collect::<Vec<_>>())
+        }
+    }
+
+    impl Strategy for Guide {
+        fn name(&self) -> String {
+            String::from("strategy guide")
+        }
+
+        fn play(&mut self) -> Play {
+            let play = self.plays.get(self.next).copied().unwrap_or(Play::Rock);
+            self.next = (self.next + 1) % self.plays.len().max(1);
+            play
+        }
+    }
+
+    /// Every round of a match, from the first player's side.
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub struct Match {
+        pub first: String,
+        pub second: String,
+        pub log: Vec<Round>,
+    }
+
+    impl Match {
+        fn count(&self, res: RPS::Result) -> usize {
+            self.log
+                .iter()
+                .filter(|round| round.round_result() == res)
+                .count()
+        }
+
+        /// What the first and second players scored.
+        pub fn scores(&self) -> (i32, i32) {
+            self.log.iter().fold((0, 0), |(first, second), round| {
+                let flipped = Round {
+                    opponent: round.me,
+                    me: round.opponent,
+                };
+                (first + round.score(), second + flipped.score())
+            })
+        }
+
+        /// How many rounds the first player won, tied and lost.
+        pub fn record(&self) -> (usize, usize, usize) {
+            (
+                self.count(RPS::Result::Win),
+                self.count(RPS::Result::Tie),
+                self.count(RPS::Result::Loss),
+            )
+        }
+    }
+
+    impl fmt::Display for Match {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "{} vs {}", self.first, self.second)?;
+            for (i, round) in self.log.iter().enumerate() {
+                writeln!(
+                    f,
+                    "{:>6}: {:?} vs {:?} ({:?})",
+                    i + 1,
+                    round.me,
+                    round.opponent,
+                    round.round_result()
+                )?;
+            }
+            let (first, second) = self.scores();
+            write!(f, "score: {} to {}", first, second)
+        }
+    }
+
+    /// Play `rounds` rounds between two players.
+    pub fn play_match(first: &mut dyn Strategy, second: &mut dyn Strategy, rounds: usize) -> Match {
+        let log = (0..rounds)
+            .map(|_| {
+                let round = Round {
+                    me: first.play(),
+                    opponent: second.play(),
+                };
+                first.observe(&round);
+                second.observe(&Round {
+                    me: round.opponent,
+                    opponent: round.me,
+                });
+                round
+            })
+            .collect();
+        Match {
+            first: first.name(),
+            second: second.name(),
+            log,
+        }
+    }
+
+    /// How one player did over a whole tournament.
+    #[derive(Debug, Default, PartialEq, Eq, Clone)]
+    pub struct Standing {
+        pub name: String,
+        pub score: i64,
+        pub wins: usize,
+        pub ties: usize,
+        pub losses: usize,
+    }
+
+    impl Standing {
+        pub fn rounds(&self) -> usize {
+            self.wins + self.ties + self.losses
+        }
+
+        /// The fraction of rounds won, or 0 if there weren't any.
+        pub fn win_rate(&self) -> f64 {
+            if self.rounds() == 0 {
+                0.0
+            } else {
+                self.wins as f64 / self.rounds() as f64
+            }
+        }
+    }
+
+    impl fmt::Display for Standing {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "{}: {} points, {}-{}-{} ({:.1}% won)",
+                self.name,
+                self.score,
+                self.wins,
+                self.ties,
+                self.losses,
+                self.win_rate() * 100.0
+            )
+        }
+    }
+
+    /// Makes a fresh player, so nobody remembers anything from their
+    /// last match.
+    pub type Entrant = Box<dyn Fn() -> Box<dyn Strategy>>;
+
+    pub struct Tournament {
+        pub matches: Vec<Match>,
+        /// In the same order as the entrants.
+        pub standings: Vec<Standing>,
+    }
+
+    /// Every entrant plays a match of `rounds` rounds against every
+    /// other entrant.
+    pub fn round_robin(entrants: &[Entrant], rounds: usize) -> Tournament {
+        let mut standings: Vec<Standing> = entrants
+            .iter()
+            .map(|entrant| Standing {
+                name: entrant().name(),
+                ..Standing::default()
+            })
+            .collect();
+        let mut matches = Vec::new();
+
+        for a in 0..entrants.len() {
+            for b in (a + 1)..entrants.len() {
+                let played = play_match(&mut *entrants[a](), &mut *entrants[b](), rounds);
+                let (first, second) = played.scores();
+                let (wins, ties, losses) = played.record();
+                for (i, score, wins, losses) in
+                    [(a, first, wins, losses), (b, second, losses, wins)]
+                {
+                    let standing = &mut standings[i];
+                    standing.score += i64::from(score);
+                    standing.wins += wins;
+                    standing.ties += ties;
+                    standing.losses += losses;
+                }
+                matches.push(played);
+            }
+        }
+
+        Tournament { matches, standings }
+    }
+}
+
+/// Split a line of the strategy guide into its two columns and parse
+/// each of them with `parse_left` and `parse_right`. Their errors only
+/// need to be placed within their own column.
+fn parse_columns<A, B>(
+    line: &str,
+    parse_left: impl FnOnce(&str) -> Result<A, ParseError>,
+    parse_right: impl FnOnce(&str) -> Result<B, ParseError>,
+) -> Result<(A, B), ParseError> {
+    let (left, right) = line
+        .split_once(' ')
+        .ok_or_else(|| ParseError::missing(2, "a second column", line))?;
+    Ok((
+        parse_left(left)?,
+        parse_right(right).map_err(|e| e.shifted(left.len() + 1))?,
+    ))
+}
+
+/// What a letter in the second column of the guide stands for.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Meaning {
+    Play(RPS::Play),
+    Result(RPS::Result),
+}
+
+/// Which letters in the guide mean what.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Cipher {
+    pub opponent: BTreeMap<char, RPS::Play>,
+    pub me: BTreeMap<char, Meaning>,
+}
+
+impl Cipher {
+    /// A, B and C are the opponent's rock, paper and scissors, and X, Y
+    /// and Z stand for whatever `me` says, in that order.
+    pub fn new(me: [Meaning; 3]) -> Self {
+        Self {
+            opponent: ['A', 'B', 'C'].into_iter().zip(RPS::Play::ALL).collect(),
+            me: ['X', 'Y', 'Z'].into_iter().zip(me).collect(),
+        }
+    }
+
+    /// What we thought the guide meant at first.
+    pub fn plays() -> Self {
+        use RPS::Play::*;
+        Self::new([Rock, Paper, Scissors].map(Meaning::Play))
+    }
+
+    /// What the elf said the guide meant.
+    pub fn results() -> Self {
+        use RPS::Result::*;
+        Self::new([Loss, Tie, Win].map(Meaning::Result))
+    }
+
+    /// Every way of reading X, Y and Z as the three plays, and then
+    /// every way of reading them as the three results.
+    pub fn all() -> Vec<Self> {
+        use RPS::Result::*;
+        let results = [Loss, Tie, Win];
+        permutations()
+            .map(|order| Self::new(order.map(|i| Meaning::Play(RPS::Play::ALL[i]))))
+            .chain(
+                permutations().map(|order| Self::new(order.map(|i| Meaning::Result(results[i])))),
+            )
+            .collect()
+    }
+
+    fn lookup<T: Copy>(letters: &BTreeMap<char, T>, text: &str) -> Result<T, ParseError> {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(letter), None) if letters.contains_key(&letter) => Ok(letters[&letter]),
+            _ => Err(ParseError::new(
+                2,
+                ParseErrorKind::Unexpected {
+                    expected: "a letter in the cipher",
+                },
+                text,
+                text,
+            )),
+        }
+    }
+
+    /// Read the guide the way this cipher says to.
+    pub fn decode(&self, input: &str) -> Result<Vec<RPS::Round>, ParseError> {
+        parse_lines(input, |l| {
+            let (opponent, meaning) = parse_columns(
+                l,
+                |left| Self::lookup(&self.opponent, left),
+                |right| Self::lookup(&self.me, right),
+            )?;
+            let me = match meaning {
+                Meaning::Play(me) => me,
+                Meaning::Result(res) => RPS::Round::from(&RPS::RoundStrategy { opponent, res }).me,
+            };
+            Ok(RPS::Round { opponent, me })
+        })
+    }
+
+    /// Our total score if the guide means what this cipher says.
+    pub fn score(&self, input: &str) -> Result<i32, ParseError> {
+        Ok(day2part1(&self.decode(input)?))
+    }
+}
+
+impl fmt::Display for Cipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let opponent = self
+            .opponent
+            .iter()
+            .map(|(c, play)| format!("{}={:?}", c, play));
+        let me = self.me.iter().map(|(c, meaning)| match meaning {
+            Meaning::Play(play) => format!("{}={:?}", c, play),
+            Meaning::Result(res) => format!("{}={:?}", c, res),
+        });
+        write!(f, "{}", opponent.chain(me).collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// All six orderings of three things.
+fn permutations() -> impl Iterator<Item = [usize; 3]> {
+    [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ]
+    .into_iter()
+}
+
+/// The score we'd get for every way of reading X, Y and Z, best first.
+pub fn explore_ciphers(input: &str) -> Result<Vec<(Cipher, i32)>, ParseError> {
+    let mut scores = Cipher::all()
+        .into_iter()
+        .map(|cipher| {
+            let score = cipher.score(input)?;
+            Ok((cipher, score))
         })
-        .collect()
+        .collect::<Result<Vec<_>, ParseError>>()?;
+    scores.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    Ok(scores)
+}
+
+#[aoc_generator(day2, part1)]
+pub fn parse_rps_strategy(input: &str) -> Result<Vec<RPS::Round>, ParseError> {
+    parse_lines(input, |l| {
+        let (opponent, me) = parse_columns(l, str::parse, str::parse)?;
+        Ok(RPS::Round { opponent, me })
+    })
 }
 
 #[aoc_generator(day2, part2)]
 #[allow(non_snake_case)]
-pub fn parse_rps_strategy_FINAL(input: &str) -> Vec<RPS::RoundStrategy> {
-    input
-        .lines()
-        .map(|l| {
-            let mut plays = l.split(' ');
-            let opp = plays.next().unwrap();
-            let res = plays.next().unwrap();
-            RPS::RoundStrategy {
-                opponent: RPS::Play::from(opp),
-                res: RPS::Result::from(res),
-            }
-        })
-        .collect()
+pub fn parse_rps_strategy_FINAL(input: &str) -> Result<Vec<RPS::RoundStrategy>, ParseError> {
+    parse_lines(input, |l| {
+        let (opponent, res) = parse_columns(l, str::parse, str::parse)?;
+        Ok(RPS::RoundStrategy { opponent, res })
+    })
 }
 
 #[aoc(day2, part1)]
@@ -162,9 +974,286 @@ fn day2part2(rounds: &[RPS::RoundStrategy]) -> i32 {
         .fold(0, |acc, r| acc + r.score())
 }
 
+pub(crate) static SOLVERS: &[&dyn Solver] = &[
+    &Solution {
+        day: 2,
+        part: 1,
+        name: "day2part1",
+        generator: parse_rps_strategy,
+        solver: |rounds| Ok(day2part1(rounds).into()),
+    },
+    &Solution {
+        day: 2,
+        part: 2,
+        name: "day2part2",
+        generator: parse_rps_strategy_FINAL,
+        solver: |rounds| Ok(day2part2(rounds).into()),
+    },
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_cyclic_game() {
+        let rps = game::Game::cyclic(3).unwrap();
+        assert_eq!(game::Outcome::Win, rps.outcome(1, 0));
+        assert_eq!(game::Outcome::Loss, rps.outcome(0, 1));
+        assert_eq!(game::Outcome::Win, rps.outcome(0, 2));
+        assert_eq!(game::Outcome::Tie, rps.outcome(2, 2));
+        assert_eq!(8, rps.score(1, 0));
+        assert_eq!(vec![2], rps.shapes_for(1, game::Outcome::Win));
+    }
+
+    #[test]
+    fn test_rock_paper_scissors_lizard_spock() {
+        const ROCK: usize = 0;
+        const PAPER: usize = 1;
+        const SCISSORS: usize = 2;
+        const SPOCK: usize = 3;
+        const LIZARD: usize = 4;
+        let rpsls = game::Game::from_beats(
+            5,
+            &[
+                (SCISSORS, PAPER),
+                (PAPER, ROCK),
+                (ROCK, LIZARD),
+                (LIZARD, SPOCK),
+                (SPOCK, SCISSORS),
+                (SCISSORS, LIZARD),
+                (LIZARD, PAPER),
+                (PAPER, SPOCK),
+                (SPOCK, ROCK),
+                (ROCK, SCISSORS),
+            ],
+        )
+        .unwrap();
+        assert_eq!(rpsls, game::Game::cyclic(5).unwrap());
+
+        let rpsls = rpsls
+            .with_shape_scores(vec![10, 20, 30, 40, 50])
+            .unwrap()
+            .with_outcome_scores(game::OutcomeScores {
+                win: 1,
+                tie: 0,
+                loss: -1,
+            });
+        assert_eq!(51, rpsls.score(LIZARD, SPOCK));
+        assert_eq!(39, rpsls.score(SPOCK, PAPER));
+        assert_eq!(
+            vec![PAPER, SPOCK],
+            rpsls.shapes_for(ROCK, game::Outcome::Win)
+        );
+    }
+
+    #[test]
+    fn test_game_errors() {
+        use game::{Game, GameError};
+        assert_eq!(Err(GameError::EvenShapes(4)), Game::cyclic(4));
+        assert_eq!(
+            Err(GameError::Undecided(0, 1)),
+            Game::from_beats(3, &[(1, 0), (0, 1), (2, 1), (0, 2)])
+        );
+        assert_eq!(
+            Err(GameError::Undecided(1, 2)),
+            Game::from_beats(3, &[(1, 0), (0, 2)])
+        );
+        assert_eq!(
+            Err(GameError::Unbalanced(0)),
+            Game::from_beats(
+                5,
+                &[
+                    (0, 1),
+                    (0, 2),
+                    (0, 3),
+                    (4, 0),
+                    (1, 2),
+                    (3, 1),
+                    (1, 4),
+                    (2, 3),
+                    (4, 2),
+                    (3, 4)
+                ]
+            )
+        );
+        assert_eq!(
+            Err(GameError::NoSuchShape(3)),
+            Game::from_beats(3, &[(0, 3)])
+        );
+        assert_eq!(
+            Err(GameError::ShapeScores {
+                shapes: 3,
+                scores: 2
+            }),
+            Game::cyclic(3).unwrap().with_shape_scores(vec![1, 2])
+        );
+    }
+
+    #[test]
+    fn test_best_plan() {
+        use game::{GameError, Plan, Rules};
+        use RPS::Play::*;
+        let opponent = [Rock, Paper, Scissors, Rock];
+
+        assert_eq!(
+            Some(Plan {
+                score: 8 + 9 + 7 + 8,
+                plays: vec![Paper, Scissors, Rock, Paper]
+            }),
+            RPS::best_plan(&opponent, &Rules::default()).unwrap()
+        );
+
+        // We can only beat one of the rocks, and it doesn't matter
+        // which, so there's more than one best plan here. We tie the
+        // other one.
+        let rules = Rules {
+            max_uses: Some(vec![4, 1, 4]),
+            no_repeats: false,
+        };
+        let plan = RPS::best_plan(&opponent, &rules).unwrap().unwrap();
+        assert_eq!(8 + 9 + 7 + 4, plan.score);
+        assert_eq!(1, plan.plays.iter().filter(|&&p| p == Paper).count());
+
+        let opponent = [Rock, Rock, Rock];
+        let rules = Rules {
+            max_uses: None,
+            no_repeats: true,
+        };
+        let plan = RPS::best_plan(&opponent, &rules).unwrap().unwrap();
+        assert_eq!(vec![Paper, Rock, Paper], plan.plays);
+        assert_eq!(8 + 4 + 8, plan.score);
+
+        let rules = Rules {
+            max_uses: Some(vec![1, 1, 0]),
+            no_repeats: true,
+        };
+        assert_eq!(None, RPS::best_plan(&opponent, &rules).unwrap());
+        assert_eq!(
+            Some(Plan {
+                score: 0,
+                plays: vec![]
+            }),
+            RPS::best_plan(&[], &rules).unwrap()
+        );
+
+        let rules = Rules {
+            max_uses: Some(vec![1, 1]),
+            no_repeats: false,
+        };
+        assert_eq!(
+            Err(GameError::Limits {
+                shapes: 3,
+                limits: 2
+            }),
+            RPS::best_plan(&opponent, &rules)
+        );
+        assert_eq!(
+            Err(GameError::NoSuchShape(3)),
+            game::Game::cyclic(3)
+                .unwrap()
+                .best_plan(&[0, 3], &Rules::default())
+        );
+    }
+
+    #[test]
+    fn test_cipher() {
+        assert_eq!(15, Cipher::plays().score(INPUT).unwrap());
+        assert_eq!(12, Cipher::results().score(INPUT).unwrap());
+        assert_eq!(
+            "A=Rock B=Paper C=Scissors X=Loss Y=Tie Z=Win",
+            Cipher::results().to_string()
+        );
+
+        let err = Cipher::plays().decode("A Y\nB W\n").unwrap_err();
+        assert_eq!((2, 3), (err.line, err.column));
+        let err = Cipher::plays().decode("AB Y\n").unwrap_err();
+        assert_eq!((1, 1, "AB"), (err.line, err.column, err.text.as_str()));
+    }
+
+    #[test]
+    fn test_explore_ciphers() {
+        let scores = explore_ciphers(INPUT).unwrap();
+        assert_eq!(12, scores.len());
+        assert!(scores.contains(&(Cipher::plays(), 15)));
+        assert!(scores.contains(&(Cipher::results(), 12)));
+        assert!(scores.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        // Reading the guide as "always win" is as good as it gets.
+        use RPS::Result::*;
+        let best = Cipher::new([Win, Win, Win].map(Meaning::Result))
+            .score(INPUT)
+            .unwrap();
+        assert!(scores[0].1 <= best);
+    }
+
+    #[test]
+    fn test_match() {
+        use tournament::*;
+        use RPS::Play::*;
+        let played = play_match(&mut Fixed(Paper), &mut Fixed(Rock), 10);
+        assert_eq!((80, 10), played.scores());
+        assert_eq!((10, 0, 0), played.record());
+        assert_eq!("always Paper", played.first);
+        assert!(played
+            .to_string()
+            .starts_with("always Paper vs always Rock\n     1: Paper vs Rock (Win)\n"));
+
+        let mut first = Random::new(7);
+        let mut second = Random::new(7);
+        let first = play_match(&mut first, &mut FrequencyCounter::default(), 100);
+        let second = play_match(&mut second, &mut FrequencyCounter::default(), 100);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_learning_strategies() {
+        use tournament::*;
+        use RPS::Play::*;
+
+        // After the first round, the counter knows what's coming.
+        let played = play_match(&mut FrequencyCounter::default(), &mut Fixed(Scissors), 100);
+        assert_eq!((99, 0, 1), played.record());
+
+        // The guide just goes round and round, which is easy to learn
+        // from what came before but not from how often things come up.
+        let guide = parse_rps_strategy(INPUT).unwrap();
+        let played = play_match(&mut Markov::default(), &mut Guide::new(&guide), 300);
+        assert!(played.record().0 > 290);
+        let played = play_match(
+            &mut FrequencyCounter::default(),
+            &mut Guide::new(&guide),
+            300,
+        );
+        assert!(played.record().0 < 150);
+    }
+
+    #[test]
+    fn test_round_robin() {
+        use tournament::*;
+        let guide = parse_rps_strategy_FINAL(INPUT).unwrap();
+        let entrants: Vec<Entrant> = vec![
+            Box::new(|| Box::new(Fixed(RPS::Play::Rock))),
+            Box::new(|| Box::new(Random::new(2022))),
+            Box::new(|| Box::new(FrequencyCounter::default())),
+            Box::new(|| Box::new(Markov::default())),
+            Box::new(move || Box::new(Guide::from_strategy(&guide))),
+        ];
+        let tournament = round_robin(&entrants, 50);
+        assert_eq!(10, tournament.matches.len());
+        for standing in &tournament.standings {
+            assert_eq!(4 * 50, standing.rounds());
+        }
+        assert_eq!("strategy guide", tournament.standings[4].name);
+
+        let wins: usize = tournament.standings.iter().map(|s| s.wins).sum();
+        let losses: usize = tournament.standings.iter().map(|s| s.losses).sum();
+        assert_eq!(wins, losses);
+        // The first entrant is the first player in each of its matches.
+        let score: i32 = tournament.matches[..4].iter().map(|m| m.scores().0).sum();
+        assert_eq!(i64::from(score), tournament.standings[0].score);
+    }
 
     #[test]
     fn test_rps_scores() {
@@ -179,7 +1268,7 @@ C Z";
 
     #[test]
     fn test_parser() {
-        let rounds = parse_rps_strategy(INPUT);
+        let rounds = parse_rps_strategy(INPUT).unwrap();
         assert_eq!(
             rounds,
             vec![
@@ -201,7 +1290,7 @@ C Z";
 
     #[test]
     fn test_results() {
-        let rounds = parse_rps_strategy(INPUT);
+        let rounds = parse_rps_strategy(INPUT).unwrap();
         let mut results = rounds.iter().map(|r| r.round_result());
         assert_eq!(results.next(), Some(RPS::Result::Win));
         assert_eq!(results.next(), Some(RPS::Result::Loss));
@@ -211,13 +1300,129 @@ C Z";
 
     #[test]
     fn test_part1() {
-        let rounds = parse_rps_strategy(INPUT);
+        let rounds = parse_rps_strategy(INPUT).unwrap();
         assert_eq!(15, day2part1(&rounds));
     }
 
     #[test]
     fn test_part2() {
-        let rounds = parse_rps_strategy_FINAL(INPUT);
+        let rounds = parse_rps_strategy_FINAL(INPUT).unwrap();
         assert_eq!(12, day2part2(&rounds));
     }
+
+    #[test]
+    fn test_parser_errors() {
+        let err = parse_rps_strategy("A Y\nB Q\nC Z").unwrap_err();
+        assert_eq!((2, 2, 3), (err.day, err.line, err.column));
+        assert_eq!("Q", err.text);
+
+        let err = parse_rps_strategy_FINAL("A Y\nB").unwrap_err();
+        assert_eq!((2, 2), (err.line, err.column));
+
+        let err = parse_rps_strategy_FINAL("A A").unwrap_err();
+        assert_eq!((1, 3), (err.line, err.column));
+    }
+
+    fn play() -> impl Strategy<Value = RPS::Play> {
+        prop_oneof![
+            Just(RPS::Play::Rock),
+            Just(RPS::Play::Paper),
+            Just(RPS::Play::Scissors)
+        ]
+    }
+
+    fn result() -> impl Strategy<Value = RPS::Result> {
+        prop_oneof![
+            Just(RPS::Result::Win),
+            Just(RPS::Result::Loss),
+            Just(RPS::Result::Tie)
+        ]
+    }
+
+    fn letter(play: RPS::Play, letters: [&str; 3]) -> &str {
+        match play {
+            RPS::Play::Rock => letters[0],
+            RPS::Play::Paper => letters[1],
+            RPS::Play::Scissors => letters[2],
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_cyclic_games_are_balanced(half in 0..10usize) {
+            let game = game::Game::cyclic(half * 2 + 1).unwrap();
+            for a in 0..game.shapes() {
+                prop_assert_eq!(half, game.shapes_for(a, game::Outcome::Win).len());
+                prop_assert_eq!(half, game.shapes_for(a, game::Outcome::Loss).len());
+            }
+        }
+
+        #[test]
+        fn prop_best_plan_is_best(
+            opponent in proptest::collection::vec(play(), 0..6),
+            no_repeats in any::<bool>(),
+        ) {
+            let rules = game::Rules { max_uses: Some(vec![2, 2, 2]), no_repeats };
+            let plan = RPS::best_plan(&opponent, &rules).unwrap();
+
+            // Try every possible sequence of plays.
+            let mut best = None;
+            for n in 0..3usize.pow(opponent.len() as u32) {
+                let plays: Vec<RPS::Play> = (0..opponent.len())
+                    .map(|i| RPS::Play::ALL[n / 3usize.pow(i as u32) % 3])
+                    .collect();
+                let allowed = RPS::Play::ALL
+                    .iter()
+                    .all(|&shape| plays.iter().filter(|&&p| p == shape).count() <= 2)
+                    && (!no_repeats || plays.windows(2).all(|w| w[0] != w[1]));
+                if allowed {
+                    let score = opponent
+                        .iter()
+                        .zip(&plays)
+                        .map(|(&opponent, &me)| RPS::Round { opponent, me }.score())
+                        .sum::<i32>();
+                    best = best.max(Some(score));
+                }
+            }
+
+            prop_assert_eq!(best, plan.as_ref().map(|plan| plan.score));
+            if let Some(plan) = plan {
+                let score = opponent
+                    .iter()
+                    .zip(&plan.plays)
+                    .map(|(&opponent, &me)| RPS::Round { opponent, me }.score())
+                    .sum::<i32>();
+                prop_assert_eq!(plan.score, score);
+            }
+        }
+
+        #[test]
+        fn prop_strategy_gets_requested_result(opponent in play(), res in result()) {
+            let round = RPS::Round::from(&RPS::RoundStrategy { opponent, res });
+            prop_assert_eq!(res, round.round_result());
+            prop_assert_eq!(round.me.score() + res.score(), round.score());
+        }
+
+        #[test]
+        fn prop_parse_round_trip(rounds in prop::collection::vec((play(), play()), 1..50)) {
+            let input = rounds
+                .iter()
+                .map(|&(opponent, me)| {
+                    format!(
+                        "{} {}",
+                        letter(opponent, ["A", "B", "C"]),
+                        letter(me, ["X", "Y", "Z"])
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            prop_assert_eq!(
+                rounds
+                    .into_iter()
+                    .map(|(opponent, me)| RPS::Round { opponent, me })
+                    .collect::<Vec<_>>(),
+                parse_rps_strategy(&input).unwrap()
+            );
+        }
+    }
 }
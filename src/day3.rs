@@ -1,21 +1,142 @@
+use crate::error::{parse_lines, ParseError, ParseErrorKind};
+use crate::registry::{Solution, Solver};
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashSet;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::ops::{BitAnd, BitOr};
+use std::str::FromStr;
 
 fn rucksack_split(contents: &str) -> (&str, &str) {
     contents.split_at(contents.len() / 2)
 }
 
-fn compartment_set(contents: &str) -> HashSet<char> {
-    contents.chars().collect::<HashSet<_>>()
+/// A set of items, stored as one bit per priority. Only letters are
+/// items, so they all fit in a `u64` with room to spare.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct ItemSet(u64);
+
+impl ItemSet {
+    pub fn new() -> Self {
+        Self(0)
+    }
+
+    /// Panics if `item` isn't a letter.
+    pub fn insert(&mut self, item: char) {
+        assert!(item.is_ascii_alphabetic(), "`{}` isn't an item", item);
+        self.0 |= 1 << priority(item);
+    }
+
+    pub fn contains(&self, item: char) -> bool {
+        item.is_ascii_alphabetic() && self.0 & (1 << priority(item)) != 0
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// The items in order of priority.
+    pub fn iter(&self) -> impl Iterator<Item = char> {
+        let bits = self.0;
+        (1..=52).filter(move |&p| bits & (1 << p) != 0).map(item)
+    }
+
+    /// The lowest priority item in the set.
+    pub fn first(&self) -> Option<char> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(item(self.0.trailing_zeros()))
+        }
+    }
+}
+
+impl BitAnd for ItemSet {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        self.intersection(other)
+    }
+}
+
+impl BitOr for ItemSet {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.union(other)
+    }
 }
 
-fn rucksack_shared_item(compartments: (&str, &str)) -> char {
-    let left = compartment_set(compartments.0);
-    let right = compartment_set(compartments.1);
-    *left.intersection(&right).into_iter().next().unwrap()
+/// Panics on anything that isn't a letter, the same as
+/// [`ItemSet::insert`]. Use `parse` for text that hasn't been checked.
+impl FromIterator<char> for ItemSet {
+    fn from_iter<I: IntoIterator<Item = char>>(items: I) -> Self {
+        let mut set = Self::new();
+        for item in items {
+            set.insert(item);
+        }
+        set
+    }
 }
 
+impl FromStr for ItemSet {
+    type Err = ParseError;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        Ok(check_items(contents)?.chars().collect())
+    }
+}
+
+impl fmt::Display for ItemSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.iter().try_for_each(|item| write!(f, "{}", item))
+    }
+}
+
+/// Only for rucksacks that have been through [`check_items`].
+fn rucksack_shared_item(compartments: (&str, &str)) -> Option<char> {
+    let left: ItemSet = compartments.0.chars().collect();
+    let right: ItemSet = compartments.1.chars().collect();
+    (left & right).first()
+}
+
+/// Make sure a rucksack only holds items we can give a priority to.
+fn check_items(contents: &str) -> Result<&str, ParseError> {
+    match contents
+        .char_indices()
+        .find(|(_, c)| !c.is_ascii_alphabetic())
+    {
+        Some((i, c)) => Err(ParseError::new(
+            3,
+            ParseErrorKind::Unexpected {
+                expected: "an item (a-z or A-Z)",
+            },
+            contents,
+            &contents[i..i + c.len_utf8()],
+        )),
+        None => Ok(contents),
+    }
+}
+
+fn no_shared_item(contents: &str, expected: &'static str) -> ParseError {
+    ParseError::new(3, ParseErrorKind::Missing { expected }, contents, contents)
+}
+
+/// Only letters have priorities; anything else has to be caught by
+/// [`check_items`] first.
 fn priority(item: char) -> u32 {
+    debug_assert!(item.is_ascii_alphabetic(), "`{}` has no priority", item);
     if item.is_ascii_lowercase() {
         u32::from(item) - 96
     } else {
@@ -23,42 +144,341 @@ fn priority(item: char) -> u32 {
     }
 }
 
+/// The item with this priority.
+fn item(priority: u32) -> char {
+    if priority <= 26 {
+        char::from(b'a' + priority as u8 - 1)
+    } else {
+        char::from(b'A' + priority as u8 - 27)
+    }
+}
+
 #[aoc_generator(day3, part1)]
-fn compartment_priorities(input: &str) -> Vec<u32> {
-    input
-        .lines()
-        .map(|l| priority(rucksack_shared_item(rucksack_split(l))))
-        .collect()
+fn compartment_priorities(input: &str) -> Result<Vec<u32>, ParseError> {
+    shared_item_priorities(input, rucksack_shared_item)
 }
 
-fn group_badge(groups: &[&str]) -> char {
-    intersect_sets(groups.iter().map(|g| g.chars().collect::<HashSet<char>>()))
-        .into_iter()
-        .next()
-        .unwrap()
-}
-
-fn intersect_sets<T>(sets: impl Iterator<Item = HashSet<T>>) -> HashSet<T>
-where
-    T: Eq,
-    T: std::hash::Hash,
-    T: Copy,
-{
-    sets.reduce(|left, right| {
-        left.intersection(&right)
-            .fold(HashSet::<T>::new(), |mut acc, t| {
-                acc.insert(*t);
-                acc
-            })
+/// The priority of the item in both compartments of each rucksack,
+/// found with `shared_item`.
+fn shared_item_priorities(
+    input: &str,
+    shared_item: fn((&str, &str)) -> Option<char>,
+) -> Result<Vec<u32>, ParseError> {
+    parse_lines(input, |l| {
+        shared_item(rucksack_split(check_items(l)?))
+            .map(priority)
+            .ok_or_else(|| no_shared_item(l, "an item in both compartments"))
     })
-    .unwrap_or_default()
 }
 
+/// Only for rucksacks that have been through [`check_items`].
+fn group_badge(groups: &[&str]) -> Option<char> {
+    groups
+        .iter()
+        .map(|g| g.chars().collect::<ItemSet>())
+        .reduce(ItemSet::intersection)?
+        .first()
+}
+
+/// How we used to find shared items, with a `HashSet` for every
+/// rucksack. It's kept around so the benches can show what `ItemSet`
+/// buys us. It isn't registered as a solver: when a rucksack (or a
+/// group) has more than one item in common, which one it picks depends
+/// on the order the `HashSet` iterates in.
+pub mod hashset {
+    use crate::error::ParseError;
+    use std::collections::HashSet;
+
+    pub fn compartment_priorities(input: &str) -> Result<Vec<u32>, ParseError> {
+        super::shared_item_priorities(input, rucksack_shared_item)
+    }
+
+    pub fn group_priorities(input: &str) -> Result<Vec<u32>, ParseError> {
+        super::badge_priorities(input, super::GROUP_SIZE, group_badge)
+    }
+
+    fn compartment_set(contents: &str) -> HashSet<char> {
+        contents.chars().collect::<HashSet<_>>()
+    }
+
+    pub(super) fn rucksack_shared_item(compartments: (&str, &str)) -> Option<char> {
+        let left = compartment_set(compartments.0);
+        let right = compartment_set(compartments.1);
+        left.intersection(&right).next().copied()
+    }
+
+    pub(super) fn group_badge(groups: &[&str]) -> Option<char> {
+        intersect_sets(groups.iter().map(|g| g.chars().collect::<HashSet<char>>()))
+            .into_iter()
+            .next()
+    }
+
+    fn intersect_sets<T>(sets: impl Iterator<Item = HashSet<T>>) -> HashSet<T>
+    where
+        T: Eq,
+        T: std::hash::Hash,
+        T: Copy,
+    {
+        sets.reduce(|left, right| {
+            left.intersection(&right)
+                .fold(HashSet::<T>::new(), |mut acc, t| {
+                    acc.insert(*t);
+                    acc
+                })
+        })
+        .unwrap_or_default()
+    }
+}
+
+/// How many elves there are in a group for part 2.
+const GROUP_SIZE: NonZeroUsize = NonZeroUsize::new(3).unwrap();
+
 #[aoc_generator(day3, part2)]
-fn group_priorities(input: &str) -> Vec<u32> {
-    let lines = input.lines().collect::<Vec<&str>>();
-    let groups = lines[..].chunks(3);
-    groups.map(|g| priority(group_badge(g))).collect()
+fn group_priorities(input: &str) -> Result<Vec<u32>, ParseError> {
+    group_priorities_of(input, GROUP_SIZE)
+}
+
+/// The priority of each group's badge, taking the elves `size` at a
+/// time. There have to be enough elves to fill the last group.
+pub fn group_priorities_of(input: &str, size: NonZeroUsize) -> Result<Vec<u32>, ParseError> {
+    badge_priorities(input, size, group_badge)
+}
+
+/// The priority of each group's badge, found with `badge`.
+fn badge_priorities(
+    input: &str,
+    size: NonZeroUsize,
+    badge: fn(&[&str]) -> Option<char>,
+) -> Result<Vec<u32>, ParseError> {
+    let size = size.get();
+    let lines = parse_lines(input, check_items)?;
+    if let Some(last) = lines.last().filter(|_| lines.len() % size != 0) {
+        return Err(ParseError::missing(3, "the rest of the last group", last).on_line(lines.len()));
+    }
+    let groups = lines[..].chunks(size);
+    groups
+        .enumerate()
+        .map(|(i, g)| {
+            badge(g).map(priority).ok_or_else(|| {
+                no_shared_item(g[0], "a badge shared by the group").on_line(i * size + 1)
+            })
+        })
+        .collect()
+}
+
+/// Elves that share exactly one item, which must be their badge.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Group {
+    /// Which lines the elves' rucksacks are on, counting from 1.
+    pub lines: Vec<usize>,
+    pub badge: char,
+}
+
+/// Split the elves into groups of `size`, in any order, so that each
+/// group shares exactly one item. Gives `None` if there's no way to do
+/// that.
+///
+/// This is a backtracking search: the first elf without a group gets
+/// grouped with each combination of the others in turn, giving up on a
+/// combination as soon as its members have nothing in common. That's
+/// usually quick, but there's nothing stopping a nasty input from
+/// making it try every partition.
+pub fn find_groups(input: &str, size: NonZeroUsize) -> Result<Option<Vec<Group>>, ParseError> {
+    let size = size.get();
+    let rucksacks = parse_lines(input, |l| l.parse::<ItemSet>())?;
+    if rucksacks.len() % size != 0 {
+        return Ok(None);
+    }
+
+    fn search(
+        rucksacks: &[ItemSet],
+        size: usize,
+        grouped: &mut [bool],
+        groups: &mut Vec<Group>,
+    ) -> bool {
+        let Some(first) = grouped.iter().position(|&g| !g) else {
+            return true;
+        };
+        grouped[first] = true;
+        let mut members = vec![first];
+        if extend(
+            rucksacks,
+            size,
+            grouped,
+            groups,
+            &mut members,
+            rucksacks[first],
+        ) {
+            return true;
+        }
+        grouped[first] = false;
+        false
+    }
+
+    /// Add elves after the last member until the group is full, then
+    /// carry on with the rest of the elves.
+    fn extend(
+        rucksacks: &[ItemSet],
+        size: usize,
+        grouped: &mut [bool],
+        groups: &mut Vec<Group>,
+        members: &mut Vec<usize>,
+        shared: ItemSet,
+    ) -> bool {
+        if members.len() == size {
+            if shared.len() != 1 {
+                return false;
+            }
+            groups.push(Group {
+                lines: members.iter().map(|&i| i + 1).collect(),
+                badge: shared.first().unwrap(),
+            });
+            if search(rucksacks, size, grouped, groups) {
+                return true;
+            }
+            groups.pop();
+            return false;
+        }
+        let after = members[members.len() - 1] + 1;
+        for i in after..rucksacks.len() {
+            let shared = shared & rucksacks[i];
+            if grouped[i] || shared.is_empty() {
+                continue;
+            }
+            grouped[i] = true;
+            members.push(i);
+            if extend(rucksacks, size, grouped, groups, members, shared) {
+                return true;
+            }
+            members.pop();
+            grouped[i] = false;
+        }
+        false
+    }
+
+    let mut grouped = vec![false; rucksacks.len()];
+    let mut groups = Vec::new();
+    Ok(search(&rucksacks, size, &mut grouped, &mut groups).then_some(groups))
+}
+
+/// Something off about a rucksack or a group of them.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Problem {
+    /// Something that isn't an item, at this column.
+    InvalidItem {
+        column: usize,
+        found: char,
+    },
+    /// The compartments can't be the same size.
+    OddLength(usize),
+    NoSharedItem,
+    /// More than one item in both compartments.
+    SharedItems(ItemSet),
+    NoBadge,
+    /// More than one item carried by the whole group.
+    SharedBadges(ItemSet),
+    /// The input ran out partway through a group, leaving only this
+    /// many rucksacks.
+    IncompleteGroup(usize),
+}
+
+/// A [`Problem`] and the line it's on. Group problems are on the
+/// group's first line.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.problem {
+            Problem::InvalidItem { column, found } => {
+                write!(f, "`{}` at column {} isn't an item", found, column)
+            }
+            Problem::OddLength(len) => {
+                write!(f, "{} items won't split into two compartments", len)
+            }
+            Problem::NoSharedItem => write!(f, "no item in both compartments"),
+            Problem::SharedItems(items) => write!(f, "{} are all in both compartments", items),
+            Problem::NoBadge => write!(f, "the group has no badge"),
+            Problem::SharedBadges(items) => {
+                write!(f, "the group could have any of {} as a badge", items)
+            }
+            Problem::IncompleteGroup(len) => write!(f, "the last group only has {} rucksacks", len),
+        }
+    }
+}
+
+/// Look over every rucksack and group for anything that would make the
+/// answer wrong or meaningless, rather than stopping at the first
+/// thing that won't parse.
+///
+/// We only look for shared items in rucksacks (and groups) where all
+/// the items are valid, and only split rucksacks of even length.
+pub fn validate(input: &str, group_size: NonZeroUsize) -> Vec<Diagnostic> {
+    let group_size = group_size.get();
+    let mut diagnostics = Vec::new();
+    let lines: Vec<&str> = input.lines().collect();
+    let mut valid = Vec::with_capacity(lines.len());
+
+    for (i, line) in lines.iter().enumerate() {
+        let mut report = |problem| {
+            diagnostics.push(Diagnostic {
+                line: i + 1,
+                problem,
+            })
+        };
+        let mut ok = true;
+        for (column, found) in line.chars().enumerate() {
+            if !found.is_ascii_alphabetic() {
+                report(Problem::InvalidItem {
+                    column: column + 1,
+                    found,
+                });
+                ok = false;
+            }
+        }
+        valid.push(ok);
+
+        let len = line.chars().count();
+        if len % 2 == 1 {
+            report(Problem::OddLength(len));
+        } else if ok {
+            let (left, right) = rucksack_split(line);
+            let shared = left.chars().collect::<ItemSet>() & right.chars().collect();
+            match shared.len() {
+                0 => report(Problem::NoSharedItem),
+                1 => {}
+                _ => report(Problem::SharedItems(shared)),
+            }
+        }
+    }
+
+    for (g, group) in lines.chunks(group_size).enumerate() {
+        let line = g * group_size + 1;
+        if group.len() < group_size {
+            diagnostics.push(Diagnostic {
+                line,
+                problem: Problem::IncompleteGroup(group.len()),
+            });
+        } else if valid[line - 1..line - 1 + group_size].iter().all(|&ok| ok) {
+            let badges = group
+                .iter()
+                .map(|rucksack| rucksack.chars().collect::<ItemSet>())
+                .fold(ItemSet(!0), ItemSet::intersection);
+            let problem = match badges.len() {
+                0 => Some(Problem::NoBadge),
+                1 => None,
+                _ => Some(Problem::SharedBadges(badges)),
+            };
+            diagnostics.extend(problem.map(|problem| Diagnostic { line, problem }));
+        }
+    }
+
+    diagnostics.sort_by_key(|d| d.line);
+    diagnostics
 }
 
 #[aoc(day3, part1)]
@@ -67,9 +487,27 @@ fn priorities_total(priorites: &[u32]) -> u32 {
     priorites.iter().sum()
 }
 
+pub(crate) static SOLVERS: &[&dyn Solver] = &[
+    &Solution {
+        day: 3,
+        part: 1,
+        name: "compartment_priorities",
+        generator: compartment_priorities,
+        solver: |priorities| Ok(priorities_total(priorities).into()),
+    },
+    &Solution {
+        day: 3,
+        part: 2,
+        name: "group_priorities",
+        generator: group_priorities,
+        solver: |priorities| Ok(priorities_total(priorities).into()),
+    },
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const INPUT: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
 jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
@@ -82,15 +520,15 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
     fn test_rucksack_split() {
         assert_eq!(
             ("vJrwpWtwJgWr", "hcsFMMfFFhFp"),
-            rucksack_split(&"vJrwpWtwJgWrhcsFMMfFFhFp")
+            rucksack_split("vJrwpWtwJgWrhcsFMMfFFhFp")
         );
     }
 
     #[test]
     fn test_rucksack_shared_item() {
         assert_eq!(
-            rucksack_shared_item(rucksack_split(&"vJrwpWtwJgWrhcsFMMfFFhFp")),
-            'p'
+            rucksack_shared_item(rucksack_split("vJrwpWtwJgWrhcsFMMfFFhFp")),
+            Some('p')
         );
     }
 
@@ -106,13 +544,16 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
 
     #[test]
     fn test_priorities_total() {
-        assert_eq!(157, priorities_total(&compartment_priorities(&INPUT)));
+        assert_eq!(
+            157,
+            priorities_total(&compartment_priorities(INPUT).unwrap())
+        );
     }
 
     #[test]
     fn test_group_badge() {
         assert_eq!(
-            'r',
+            Some('r'),
             group_badge(&[
                 "vJrwpWtwJgWrhcsFMMfFFhFp",
                 "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
@@ -120,7 +561,7 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
             ])
         );
         assert_eq!(
-            'Z',
+            Some('Z'),
             group_badge(&[
                 "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn",
                 "ttgJtRGJQctTZtZT",
@@ -129,8 +570,277 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
         );
     }
 
+    #[test]
+    fn test_item_set() {
+        let set: ItemSet = "vJrwpWtwJgWr".parse().unwrap();
+        assert_eq!(8, set.len());
+        assert_eq!("gprtvwJW", set.to_string());
+        assert!(set.contains('J'));
+        assert!(!set.contains('j'));
+        assert!(!set.contains('!'));
+        assert_eq!(Some('g'), set.first());
+
+        let other: ItemSet = "hcsFMMfFFhFp".parse().unwrap();
+        assert_eq!("p", (set & other).to_string());
+        assert_eq!(set.len() + other.len() - 1, (set | other).len());
+        assert_eq!(None, ItemSet::new().first());
+        assert!(ItemSet::new().is_empty());
+
+        let everything: ItemSet = ITEMS.parse().unwrap();
+        assert_eq!(
+            ITEMS.chars().collect::<Vec<_>>(),
+            everything.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(Some('a'), everything.first());
+
+        let err = "abc1".parse::<ItemSet>().unwrap_err();
+        assert_eq!((4, "1"), (err.column, err.text.as_str()));
+    }
+
+    #[test]
+    fn test_hashset_matches_item_set() {
+        for line in INPUT.lines() {
+            assert_eq!(
+                hashset::rucksack_shared_item(rucksack_split(line)),
+                rucksack_shared_item(rucksack_split(line))
+            );
+        }
+        let lines: Vec<&str> = INPUT.lines().collect();
+        for group in lines.chunks(3) {
+            assert_eq!(hashset::group_badge(group), group_badge(group));
+        }
+    }
+
+    #[test]
+    fn test_hashset_generators() {
+        assert_eq!(
+            compartment_priorities(INPUT).unwrap(),
+            hashset::compartment_priorities(INPUT).unwrap()
+        );
+        assert_eq!(
+            group_priorities(INPUT).unwrap(),
+            hashset::group_priorities(INPUT).unwrap()
+        );
+    }
+
     #[test]
     fn test_group_priority_total() {
-        assert_eq!(70, priorities_total(&group_priorities(&INPUT)));
+        assert_eq!(70, priorities_total(&group_priorities(INPUT).unwrap()));
+    }
+
+    #[test]
+    fn test_priority_errors() {
+        let err = compartment_priorities("vJrwpWtwJgWrhcsFMMfFFhFp\nabcd").unwrap_err();
+        assert_eq!((3, 2, 1), (err.day, err.line, err.column));
+
+        let err = compartment_priorities("vJrw pWtwJgWrhcsFMMfFFhFp").unwrap_err();
+        assert_eq!((1, 5), (err.line, err.column));
+        assert_eq!(" ", err.text);
+
+        let err = group_priorities("abc\nade\naf\nab\ncd\nef").unwrap_err();
+        assert_eq!((4, 1), (err.line, err.column));
+    }
+
+    #[test]
+    fn test_group_sizes() {
+        assert_eq!(
+            vec![2, 4],
+            group_priorities_of("ab\nbc\ncd\nde", size(2)).unwrap()
+        );
+        assert_eq!(
+            group_priorities(INPUT).unwrap(),
+            group_priorities_of(INPUT, size(3)).unwrap()
+        );
+        assert!(group_priorities_of(INPUT, size(6)).is_err());
+
+        let err = group_priorities_of("ab\nbc\ncd", size(2)).unwrap_err();
+        assert_eq!((3, 3), (err.line, err.column));
+        let err = group_priorities("abcd").unwrap_err();
+        assert_eq!(
+            "day 3, line 1, column 5: missing the rest of the last group",
+            err.to_string()
+        );
+
+        assert_eq!(
+            vec![
+                Diagnostic {
+                    line: 1,
+                    problem: Problem::NoBadge
+                },
+                Diagnostic {
+                    line: 5,
+                    problem: Problem::IncompleteGroup(2)
+                }
+            ],
+            validate(INPUT, size(4))
+        );
+    }
+
+    #[test]
+    fn test_find_groups() {
+        let groups = find_groups(INPUT, size(3)).unwrap().unwrap();
+        assert_eq!(
+            vec![
+                Group {
+                    lines: vec![1, 2, 3],
+                    badge: 'r'
+                },
+                Group {
+                    lines: vec![4, 5, 6],
+                    badge: 'Z'
+                }
+            ],
+            groups
+        );
+
+        // Taken in order, the first pair shares both a and b, but
+        // there's another way to pair them up.
+        let groups = find_groups("abx\naby\nyz\ncx", size(2)).unwrap().unwrap();
+        assert_eq!(
+            vec![
+                Group {
+                    lines: vec![1, 4],
+                    badge: 'x'
+                },
+                Group {
+                    lines: vec![2, 3],
+                    badge: 'y'
+                }
+            ],
+            groups
+        );
+
+        assert_eq!(None, find_groups("ab\nab", size(2)).unwrap());
+        assert_eq!(None, find_groups(INPUT, size(4)).unwrap());
+        assert!(find_groups("ab\na1", size(2)).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Vec::<Diagnostic>::new(), validate(INPUT, size(3)));
+
+        let diagnostics = validate("abcA1a\nabcab\nabcdbc\nabca\ndbeb", size(3));
+        let problems: Vec<(usize, Problem)> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.problem.clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    1,
+                    Problem::InvalidItem {
+                        column: 5,
+                        found: '1'
+                    }
+                ),
+                (2, Problem::OddLength(5)),
+                (3, Problem::SharedItems("bc".parse().unwrap())),
+                (4, Problem::IncompleteGroup(2)),
+            ],
+            problems
+        );
+        assert_eq!(
+            vec![Diagnostic {
+                line: 1,
+                problem: Problem::NoBadge
+            }],
+            validate("abca\ndbeb\nxyzx", size(3))
+        );
+        assert_eq!(
+            "line 3: bc are all in both compartments",
+            diagnostics[2].to_string()
+        );
+
+        let diagnostics = validate("abab\nabab\nabab\ncdef", size(3));
+        assert_eq!(
+            vec![
+                Diagnostic {
+                    line: 1,
+                    problem: Problem::SharedItems("ab".parse().unwrap())
+                },
+                Diagnostic {
+                    line: 1,
+                    problem: Problem::SharedBadges("ab".parse().unwrap())
+                },
+                Diagnostic {
+                    line: 2,
+                    problem: Problem::SharedItems("ab".parse().unwrap())
+                },
+                Diagnostic {
+                    line: 3,
+                    problem: Problem::SharedItems("ab".parse().unwrap())
+                },
+                Diagnostic {
+                    line: 4,
+                    problem: Problem::NoSharedItem
+                },
+                Diagnostic {
+                    line: 4,
+                    problem: Problem::IncompleteGroup(1)
+                },
+            ],
+            diagnostics
+        );
+    }
+
+    fn size(n: usize) -> NonZeroUsize {
+        NonZeroUsize::new(n).unwrap()
+    }
+
+    const ITEMS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+    /// A rucksack with exactly one item in both compartments. Everything
+    /// else in the left compartment comes from the even-numbered items
+    /// and everything else in the right from the odd-numbered ones.
+    fn rucksack() -> impl Strategy<Value = (String, char)> {
+        (
+            0..52usize,
+            prop::collection::vec((0..26usize, 0..26usize), 0..20),
+        )
+            .prop_map(|(shared, others)| {
+                let item = |i: usize| ITEMS.chars().nth(i).unwrap();
+                let shared = item(shared);
+                let (mut left, mut right): (String, String) = others
+                    .iter()
+                    .map(|&(l, r)| (item(l * 2), item(r * 2 + 1)))
+                    .filter(|&(l, r)| l != shared && r != shared)
+                    .unzip();
+                left.push(shared);
+                right.insert(0, shared);
+                (left + &right, shared)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_item_set_matches_hashset(
+            left in prop::collection::vec(0..52usize, 0..30),
+            right in prop::collection::vec(0..52usize, 0..30),
+        ) {
+            use std::collections::HashSet;
+            let item = |&i: &usize| ITEMS.chars().nth(i).unwrap();
+            let left_set: HashSet<char> = left.iter().map(item).collect();
+            let right_set: HashSet<char> = right.iter().map(item).collect();
+            let left: ItemSet = left.iter().map(item).collect();
+            let right: ItemSet = right.iter().map(item).collect();
+
+            let mut both: Vec<char> = left_set.intersection(&right_set).copied().collect();
+            both.sort_by_key(|&c| priority(c));
+            prop_assert_eq!(both, (left & right).iter().collect::<Vec<_>>());
+            prop_assert_eq!(left_set.union(&right_set).count(), (left | right).len());
+        }
+
+        #[test]
+        fn prop_compartment_priorities(rucksacks in prop::collection::vec(rucksack(), 1..20)) {
+            let input = rucksacks
+                .iter()
+                .map(|(line, _)| line.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            prop_assert_eq!(
+                rucksacks.iter().map(|&(_, shared)| priority(shared)).collect::<Vec<_>>(),
+                compartment_priorities(&input).unwrap()
+            );
+        }
     }
 }
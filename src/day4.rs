@@ -1,45 +1,77 @@
+use crate::error::{parse_lines, parse_number, ParseError, ParseErrorKind};
+use crate::interval::{coverage, Interval, IntervalSet, IntervalTree};
+use crate::registry::{Solution, Solver};
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::ops::RangeInclusive;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, PartialEq)]
-struct Assignment {
-    rooms: RangeInclusive<u32>,
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Assignment {
+    pub rooms: Interval<u32>,
 }
 
 impl Assignment {
-    fn new(start: u32, end: u32) -> Self {
+    /// Panics if `end` comes before `start`.
+    pub fn new(start: u32, end: u32) -> Self {
         Self {
-            rooms: (start..=end),
+            rooms: Interval::new(start, end).expect("assignments can't end before they start"),
         }
     }
 
-    fn fully_contains(&self, other: &Assignment) -> bool {
-        self.rooms.contains(other.rooms.start()) && self.rooms.contains(other.rooms.end())
+    pub fn fully_contains(&self, other: &Assignment) -> bool {
+        self.rooms.contains_interval(&other.rooms)
     }
 
-    fn overlaps(&self, other: &Assignment) -> bool {
-        self.rooms.contains(other.rooms.start()) || other.rooms.contains(self.rooms.start())
+    pub fn overlaps(&self, other: &Assignment) -> bool {
+        self.rooms.overlaps(&other.rooms)
     }
 }
 
-impl From<&str> for Assignment {
-    fn from(s: &str) -> Self {
-        let (start, end) = s.split_once('-').unwrap();
-        Self::new(start.parse().unwrap(), end.parse().unwrap())
+impl FromStr for Assignment {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| ParseError::missing(4, "`-` between the sections", s))?;
+        let rooms = Interval::new(parse_number(4, s, start)?, parse_number(4, s, end)?);
+        let rooms = rooms.ok_or_else(|| {
+            ParseError::new(
+                4,
+                ParseErrorKind::Unexpected {
+                    expected: "a section no lower than the first",
+                },
+                s,
+                end,
+            )
+        })?;
+        Ok(Self { rooms })
+    }
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rooms)
     }
 }
 
-fn parse_pair(line: &str) -> (Assignment, Assignment) {
-    let assignments = line.split_once(',').unwrap();
-    (
-        Assignment::from(assignments.0),
-        Assignment::from(assignments.1),
-    )
+pub fn parse_pair(line: &str) -> Result<(Assignment, Assignment), ParseError> {
+    let assignments = line
+        .split_once(',')
+        .ok_or_else(|| ParseError::missing(4, "`,` between the assignments", line))?;
+    Ok((
+        assignments.0.parse()?,
+        assignments
+            .1
+            .parse()
+            .map_err(|e: ParseError| e.shifted(assignments.0.len() + 1))?,
+    ))
 }
 
 #[aoc_generator(day4)]
-fn parse_assignments(input: &str) -> Vec<(Assignment, Assignment)> {
-    input.lines().map(parse_pair).collect()
+pub fn parse_assignments(input: &str) -> Result<Vec<(Assignment, Assignment)>, ParseError> {
+    parse_lines(input, parse_pair)
 }
 
 #[aoc(day4, part1)]
@@ -58,9 +90,241 @@ fn count_overlaps(assignment_pairs: &[(Assignment, Assignment)]) -> usize {
         .count()
 }
 
+/// The sections, from the lowest anyone's assigned to to the highest,
+/// that exactly `elves` elves are assigned to. With 0, that's the gaps
+/// nobody is covering.
+pub fn sections_covered_by(pairs: &[(Assignment, Assignment)], elves: usize) -> IntervalSet<u32> {
+    coverage(pairs.iter().flat_map(|(a, b)| [a.rooms, b.rooms]))
+        .into_iter()
+        .filter(|&(_, count)| count == elves)
+        .map(|(sections, _)| sections)
+        .collect()
+}
+
+/// One elf out of the whole list: which pair they're in (counting from
+/// 0, so it's one less than the line number) and whether they're first
+/// or second in it (0 or 1).
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct Elf {
+    pub pair: usize,
+    pub position: usize,
+}
+
+/// Every elf's assignment, indexed so we can ask who's assigned to a
+/// section, or to any of a range of sections.
+pub fn index(pairs: &[(Assignment, Assignment)]) -> IntervalTree<u32, Elf> {
+    pairs
+        .iter()
+        .enumerate()
+        .flat_map(|(pair, (a, b))| {
+            [
+                (a.rooms, Elf { pair, position: 0 }),
+                (b.rooms, Elf { pair, position: 1 }),
+            ]
+        })
+        .collect()
+}
+
+/// What we can learn by looking at every assignment at once, not just
+/// the ones in the same pair.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Sweep {
+    /// The most elves assigned to any one section.
+    pub most_elves: usize,
+    /// The sections with that many elves.
+    pub busiest: IntervalSet<u32>,
+    /// Every `(outer, inner)` where `outer`'s assignment fully contains
+    /// `inner`'s and they're in different pairs, in order. Elves with
+    /// the same assignment contain each other.
+    pub containments: Vec<(Elf, Elf)>,
+}
+
+/// Sweep across every assignment in order of where they start.
+///
+/// Sorting takes O(n log n), and so does counting elves per section,
+/// since that only has to look at where assignments start and end.
+/// For containments we go through the assignments from the lowest
+/// start up (and for the same start, from the highest end down), so
+/// everything that could contain an assignment has already been seen;
+/// the ones that do are the ones that end no earlier, which we can
+/// look up by end. That makes it O(n log n) plus however many
+/// containments there are, which can't be helped if we want them all.
+pub fn sweep(pairs: &[(Assignment, Assignment)]) -> Sweep {
+    let mut elves: Vec<(Elf, Interval<u32>)> = pairs
+        .iter()
+        .enumerate()
+        .flat_map(|(pair, (a, b))| {
+            [(0, a), (1, b)]
+                .map(|(position, assignment)| (Elf { pair, position }, assignment.rooms))
+        })
+        .collect();
+
+    let stretches = coverage(elves.iter().map(|&(_, rooms)| rooms));
+    let most_elves = stretches.iter().map(|&(_, count)| count).max().unwrap_or(0);
+    let busiest = stretches
+        .into_iter()
+        .filter(|&(_, count)| count == most_elves && count > 0)
+        .map(|(sections, _)| sections)
+        .collect();
+
+    elves.sort_by_key(|&(elf, rooms)| (rooms.start(), std::cmp::Reverse(rooms.end()), elf));
+    let mut containments = Vec::new();
+    let mut seen: BTreeMap<u32, Vec<Elf>> = BTreeMap::new();
+    for (i, &(inner, rooms)) in elves.iter().enumerate() {
+        let outers = seen.range(rooms.end()..).flat_map(|(_, elves)| elves);
+        containments.extend(
+            outers
+                .filter(|outer| outer.pair != inner.pair)
+                .map(|&outer| (outer, inner)),
+        );
+        // Anything with the same assignment that we haven't got to yet
+        // contains this one too.
+        containments.extend(
+            elves[i + 1..]
+                .iter()
+                .take_while(|&&(_, other)| other == rooms)
+                .filter(|(outer, _)| outer.pair != inner.pair)
+                .map(|&(outer, _)| (outer, inner)),
+        );
+        seen.entry(rooms.end()).or_default().push(inner);
+    }
+    containments.sort_unstable();
+
+    Sweep {
+        most_elves,
+        busiest,
+        containments,
+    }
+}
+
+/// New assignments for every pair, so nobody in a pair overlaps.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Reassignment {
+    pub pairs: Vec<(Assignment, Assignment)>,
+    /// How far all the starts and ends moved, added up.
+    pub cost: u64,
+}
+
+/// Prints the new assignments in the same format as the input.
+impl fmt::Display for Reassignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (a, b) in &self.pairs {
+            writeln!(f, "{},{}", a, b)?;
+        }
+        Ok(())
+    }
+}
+
+/// How far the ends of an assignment have to move to make it `to`.
+fn moves(from: &Assignment, to: Interval<u32>) -> u64 {
+    u64::from(from.rooms.start().abs_diff(to.start()))
+        + u64::from(from.rooms.end().abs_diff(to.end()))
+}
+
+/// Split `sections` between `left` and `right`, with `left` getting the
+/// first part, as cheaply as possible. Gives back the cost and the
+/// split.
+fn split(
+    left: &Assignment,
+    right: &Assignment,
+    sections: Interval<u32>,
+) -> (u64, Interval<u32>, Interval<u32>) {
+    let (first, last) = (sections.start(), sections.end() - 1);
+    // The cost is how far `left`'s end moves plus how far `right`'s
+    // start moves, and both of those only change direction where the
+    // split lines up with an original end or start, so the best split
+    // is at one of those or at the edge of what's allowed.
+    let candidates = [
+        left.rooms.end(),
+        right.rooms.start().saturating_sub(1),
+        first,
+        last,
+    ];
+    candidates
+        .into_iter()
+        .map(|end| end.clamp(first, last))
+        .map(|end| {
+            let (l, r) = (
+                Interval::new(first, end).unwrap(),
+                Interval::new(end + 1, sections.end()).unwrap(),
+            );
+            (moves(left, l) + moves(right, r), l, r)
+        })
+        .min_by_key(|&(cost, l, _)| (cost, l.end()))
+        .unwrap()
+}
+
+/// Fix every pair whose assignments overlap, moving the starts and ends
+/// as little as we can.
+///
+/// Everything a pair covered before stays covered, and since two
+/// intervals that don't overlap can't cover one stretch of sections
+/// without splitting it, that's what we do: one elf gets the start of
+/// it and the other gets the rest. We try it both ways round and at
+/// every split where the cost could be lowest, and keep the cheapest,
+/// preferring to keep whoever started first on the left.
+///
+/// The one thing we can't split is a single section both elves have.
+/// The first elf keeps it and the second moves up to the next one
+/// (or down, if there isn't a next one).
+pub fn plan_reassignments(pairs: &[(Assignment, Assignment)]) -> Reassignment {
+    let mut cost = 0;
+    let pairs = pairs
+        .iter()
+        .map(|(a, b)| {
+            if !a.overlaps(b) {
+                return (*a, *b);
+            }
+            let sections = a.rooms.union(&b.rooms).unwrap();
+            if sections.len() == 1 {
+                let s = sections.start();
+                let moved = s.checked_add(1).unwrap_or(s - 1);
+                cost += 2;
+                return (*a, Assignment::new(moved, moved));
+            }
+
+            let a_first = split(a, b, sections);
+            let (b_cost, b_new, a_new) = split(b, a, sections);
+            let b_first = (b_cost, a_new, b_new);
+            // On a tie, whoever started first stays on the left.
+            let (preferred, other) = if a.rooms.start() <= b.rooms.start() {
+                (a_first, b_first)
+            } else {
+                (b_first, a_first)
+            };
+            let (c, a_new, b_new) = if other.0 < preferred.0 {
+                other
+            } else {
+                preferred
+            };
+            cost += c;
+            (Assignment { rooms: a_new }, Assignment { rooms: b_new })
+        })
+        .collect();
+    Reassignment { pairs, cost }
+}
+
+pub(crate) static SOLVERS: &[&dyn Solver] = &[
+    &Solution {
+        day: 4,
+        part: 1,
+        name: "count_full_containments",
+        generator: parse_assignments,
+        solver: |pairs| Ok(count_full_containments(pairs).into()),
+    },
+    &Solution {
+        day: 4,
+        part: 2,
+        name: "count_overlaps",
+        generator: parse_assignments,
+        solver: |pairs| Ok(count_overlaps(pairs).into()),
+    },
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const INPUT: &str = "2-4,6-8
 2-3,4-5
@@ -71,14 +335,25 @@ mod tests {
 
     #[test]
     fn test_assignment_from_str() {
-        assert_eq!(Assignment::new(2, 4), Assignment::from("2-4"));
+        assert_eq!(Assignment::new(2, 4), "2-4".parse().unwrap());
+    }
+
+    #[test]
+    fn test_assignment_from_str_errors() {
+        let err = "2-x".parse::<Assignment>().unwrap_err();
+        assert_eq!((4, 1, 3), (err.day, err.line, err.column));
+        assert_eq!("x", err.text);
+        assert!("24".parse::<Assignment>().is_err());
+
+        let err = "6-4".parse::<Assignment>().unwrap_err();
+        assert_eq!((3, "4"), (err.column, err.text.as_str()));
     }
 
     #[test]
     fn test_pair_from_str() {
         assert_eq!(
             (Assignment::new(2, 8), Assignment::new(3, 7)),
-            parse_pair("2-8,3-7")
+            parse_pair("2-8,3-7").unwrap()
         );
     }
 
@@ -93,15 +368,24 @@ mod tests {
                 (Assignment::new(6, 6), Assignment::new(4, 6)),
                 (Assignment::new(2, 6), Assignment::new(4, 8))
             ],
-            parse_assignments(&INPUT)
+            parse_assignments(INPUT).unwrap()
         );
     }
 
+    #[test]
+    fn test_generator_errors() {
+        let err = parse_assignments("2-4,6-8\n2-3,4-y").unwrap_err();
+        assert_eq!((2, 7), (err.line, err.column));
+
+        let err = parse_assignments("2-4,6-8\n2-3").unwrap_err();
+        assert_eq!((2, 4), (err.line, err.column));
+    }
+
     #[test]
     fn test_count_full_containments() {
         assert_eq!(
             2,
-            count_full_containments(&vec![
+            count_full_containments(&[
                 (Assignment::new(2, 4), Assignment::new(6, 8)),
                 (Assignment::new(2, 3), Assignment::new(4, 5)),
                 (Assignment::new(5, 7), Assignment::new(7, 9)),
@@ -130,4 +414,196 @@ mod tests {
         assert!(Assignment::new(6, 6).overlaps(&Assignment::new(4, 6)));
         assert!(Assignment::new(2, 6).overlaps(&Assignment::new(4, 8)));
     }
+
+    #[test]
+    fn test_sections_covered_by() {
+        let pairs = parse_assignments(INPUT).unwrap();
+        assert_eq!("", sections_covered_by(&pairs, 0).to_string());
+        assert_eq!("9-9", sections_covered_by(&pairs, 1).to_string());
+        assert_eq!("2-2,8-8", sections_covered_by(&pairs, 4).to_string());
+
+        let pairs = parse_assignments("2-4,10-12\n3-3,12-13").unwrap();
+        assert_eq!("5-9", sections_covered_by(&pairs, 0).to_string());
+        assert_eq!(
+            "2-2,4-4,10-11,13-13",
+            sections_covered_by(&pairs, 1).to_string()
+        );
+        assert_eq!("3-3,12-12", sections_covered_by(&pairs, 2).to_string());
+    }
+
+    #[test]
+    fn test_sweep() {
+        let pairs = parse_assignments("2-4,6-8\n3-3,7-9\n2-4,1-1").unwrap();
+        let elf = |pair, position| Elf { pair, position };
+        assert_eq!(
+            Sweep {
+                most_elves: 3,
+                busiest: [Interval::point(3)].into_iter().collect(),
+                containments: vec![
+                    (elf(0, 0), elf(1, 0)),
+                    (elf(0, 0), elf(2, 0)),
+                    (elf(2, 0), elf(0, 0)),
+                    (elf(2, 0), elf(1, 0)),
+                ],
+            },
+            sweep(&pairs)
+        );
+
+        let sweep = sweep(&parse_assignments(INPUT).unwrap());
+        assert_eq!(8, sweep.most_elves);
+        assert_eq!("6-6", sweep.busiest.to_string());
+    }
+
+    #[test]
+    fn test_index() {
+        let index = index(&parse_assignments(INPUT).unwrap());
+        let elves = |found: Vec<&(Interval<u32>, Elf)>| {
+            let mut elves: Vec<(usize, usize)> = found
+                .iter()
+                .map(|(_, elf)| (elf.pair, elf.position))
+                .collect();
+            elves.sort();
+            elves
+        };
+        assert_eq!(vec![(2, 1)], elves(index.stabbing(9)));
+        assert_eq!(
+            vec![(0, 1), (2, 1), (3, 0), (5, 1)],
+            elves(index.overlapping(Interval::new(8, 20).unwrap()))
+        );
+        assert!(index.stabbing(1).is_empty());
+    }
+
+    #[test]
+    fn test_plan_reassignments() {
+        let plan = plan_reassignments(&parse_assignments(INPUT).unwrap());
+        assert_eq!(
+            "2-4,6-8\n2-3,4-5\n5-6,7-9\n2-2,3-8\n6-6,4-5\n2-3,4-8\n",
+            plan.to_string()
+        );
+        assert_eq!(1 + 7 + 1 + 3, plan.cost);
+        assert_eq!(0, count_overlaps(&plan.pairs));
+        assert_eq!(plan.pairs, parse_assignments(&plan.to_string()).unwrap());
+
+        let plan = plan_reassignments(
+            &parse_assignments("5-5,5-5\n4294967295-4294967295,4294967295-4294967295").unwrap(),
+        );
+        assert_eq!(
+            "5-5,6-6\n4294967295-4294967295,4294967294-4294967294\n",
+            plan.to_string()
+        );
+        assert_eq!(4, plan.cost);
+    }
+
+    fn assignment() -> impl Strategy<Value = Assignment> {
+        (0..100u32, 0..100u32).prop_map(|(a, b)| Assignment::new(a.min(b), a.max(b)))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_overlaps_is_symmetric(a in assignment(), b in assignment()) {
+            prop_assert_eq!(a.overlaps(&b), b.overlaps(&a));
+        }
+
+        #[test]
+        fn prop_fully_contains_implies_overlaps(a in assignment(), b in assignment()) {
+            if a.fully_contains(&b) {
+                prop_assert!(a.overlaps(&b));
+            }
+        }
+
+        #[test]
+        fn prop_sweep_matches_naive(pairs in prop::collection::vec((assignment(), assignment()), 0..12)) {
+            let elves: Vec<(Elf, &Assignment)> = pairs
+                .iter()
+                .enumerate()
+                .flat_map(|(pair, (a, b))| [(Elf { pair, position: 0 }, a), (Elf { pair, position: 1 }, b)])
+                .collect();
+            let mut containments = Vec::new();
+            for &(outer, a) in &elves {
+                for &(inner, b) in &elves {
+                    if outer.pair != inner.pair && a.fully_contains(b) {
+                        containments.push((outer, inner));
+                    }
+                }
+            }
+            let most_elves = (0..100)
+                .map(|section| elves.iter().filter(|(_, a)| a.rooms.contains(section)).count())
+                .max()
+                .unwrap();
+
+            let sweep = sweep(&pairs);
+            prop_assert_eq!(containments, sweep.containments);
+            prop_assert_eq!(most_elves, sweep.most_elves);
+            for section in sweep.busiest.iter().flat_map(|&i| std::ops::RangeInclusive::from(i)) {
+                prop_assert_eq!(
+                    most_elves,
+                    elves.iter().filter(|(_, a)| a.rooms.contains(section)).count()
+                );
+            }
+        }
+
+        #[test]
+        fn prop_index_matches_overlaps(
+            pairs in prop::collection::vec((assignment(), assignment()), 0..30),
+            query in assignment(),
+        ) {
+            let naive: Vec<Elf> = pairs
+                .iter()
+                .enumerate()
+                .flat_map(|(pair, (a, b))| [(Elf { pair, position: 0 }, a), (Elf { pair, position: 1 }, b)])
+                .filter(|(_, a)| a.overlaps(&query))
+                .map(|(elf, _)| elf)
+                .collect();
+            let mut found: Vec<Elf> = index(&pairs)
+                .overlapping(query.rooms)
+                .into_iter()
+                .map(|&(_, elf)| elf)
+                .collect();
+            found.sort();
+            prop_assert_eq!(naive, found);
+
+            let point = query.rooms.start();
+            let naive = pairs
+                .iter()
+                .flat_map(|(a, b)| [a, b])
+                .filter(|a| a.rooms.contains(point))
+                .count();
+            prop_assert_eq!(naive, index(&pairs).stabbing(point).len());
+        }
+
+        #[test]
+        fn prop_reassignment_is_cheapest_fix(a in assignment(), b in assignment()) {
+            let plan = plan_reassignments(&[(a, b)]);
+            let (new_a, new_b) = &plan.pairs[0];
+            prop_assert!(!new_a.overlaps(new_b));
+            for section in 0..100 {
+                if a.rooms.contains(section) || b.rooms.contains(section) {
+                    prop_assert!(new_a.rooms.contains(section) || new_b.rooms.contains(section));
+                }
+            }
+            prop_assert_eq!(moves(&a, new_a.rooms) + moves(&b, new_b.rooms), plan.cost);
+
+            // No other way of splitting what they cover is any cheaper.
+            if let Some(u) = a.rooms.union(&b.rooms).filter(|u| u.len() > 1) {
+                for k in u.start()..u.end() {
+                    let x = Interval::new(u.start(), k).unwrap();
+                    let y = Interval::new(k + 1, u.end()).unwrap();
+                    prop_assert!(plan.cost <= moves(&a, x) + moves(&b, y));
+                    prop_assert!(plan.cost <= moves(&a, y) + moves(&b, x));
+                }
+            }
+        }
+
+        #[test]
+        fn prop_parse_pair_round_trip(a in assignment(), b in assignment()) {
+            let line = format!(
+                "{}-{},{}-{}",
+                a.rooms.start(),
+                a.rooms.end(),
+                b.rooms.start(),
+                b.rooms.end()
+            );
+            prop_assert_eq!((a, b), parse_pair(&line).unwrap());
+        }
+    }
 }
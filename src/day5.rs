@@ -1,105 +1,464 @@
+use crate::error::{parse_lines, parse_number, ParseError, ParseErrorKind, SolveError};
+use crate::registry::{Solution, Solver};
 use aoc_runner_derive::{aoc, aoc_generator};
 use regex::Regex;
 use std::collections::VecDeque;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::ops::Range;
 
-type Stacks = Vec<VecDeque<char>>;
-type Moves = Vec<(usize, usize, usize)>;
+/// What's written on a crate. It's usually one letter, but there's
+/// nothing stopping it from being longer.
+pub type Crate = String;
+/// Each stack, from the top crate down.
+pub type Stacks = Vec<VecDeque<Crate>>;
+/// How many crates to move, and the stack numbers (counting from 1) to
+/// move them from and to.
+pub type Moves = Vec<(usize, usize, usize)>;
 
-fn split_input(input: &str) -> (&str, &str) {
-    input.split_once("\n\n").unwrap()
+fn split_input(input: &str) -> Result<(&str, &str), ParseError> {
+    input.split_once("\n\n").ok_or_else(|| {
+        let last = input.lines().last().unwrap_or("");
+        ParseError::missing(5, "a blank line after the stacks", last)
+            .on_line(input.lines().count().max(1))
+    })
 }
 
-fn stack_count(stacks_layout: &str) -> usize {
-    (stacks_layout.lines().next().unwrap().len() + 1) / 4
+/// Where each stack's number is in the label row under the diagram,
+/// as byte ranges. The numbers have to go 1, 2, 3, ... in order.
+fn parse_labels(line: &str) -> Result<Vec<Range<usize>>, ParseError> {
+    let mut labels = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+        let len = rest[start..]
+            .find(char::is_whitespace)
+            .unwrap_or(rest.len() - start);
+        let label = &rest[start..start + len];
+        let offset = line.len() - rest.len();
+        if label.parse::<usize>().ok() != Some(labels.len() + 1) {
+            return Err(ParseError::new(
+                5,
+                ParseErrorKind::Unexpected {
+                    expected: "the next stack number",
+                },
+                line,
+                label,
+            ));
+        }
+        labels.push(offset + start..offset + start + len);
+        rest = &rest[start + len..];
+    }
+    if labels.is_empty() {
+        return Err(ParseError::missing(5, "stack numbers", line));
+    }
+    Ok(labels)
 }
 
-fn parse_stacks(stacks_layout: &str) -> Stacks {
-    let count = stack_count(stacks_layout);
-    let mut stacks = Vec::with_capacity(count);
-
-    for _ in 0..count {
-        stacks.push(VecDeque::new());
+/// The crates on one line of the diagram, as the byte range from `[`
+/// to `]` and what's between them.
+fn parse_crates(line: &str) -> Result<Vec<(Range<usize>, &str)>, ParseError> {
+    let unexpected = |text| {
+        ParseError::new(
+            5,
+            ParseErrorKind::Unexpected {
+                expected: "a crate like `[A]`",
+            },
+            line,
+            text,
+        )
+    };
+    let mut crates = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+        let offset = line.len() - rest.len() + start;
+        let token = &rest[start..];
+        let len = token.find(char::is_whitespace).unwrap_or(token.len());
+        let label = token[..len]
+            .strip_prefix('[')
+            .and_then(|t| t.strip_suffix(']'))
+            .filter(|l| !l.is_empty() && !l.contains(['[', ']']));
+        match label {
+            Some(label) => crates.push((offset..offset + len, label)),
+            None => return Err(unexpected(&token[..len])),
+        }
+        rest = &token[len..];
     }
+    Ok(crates)
+}
 
-    for line in stacks_layout.lines() {
-        let mut chars = line.chars().skip(1).step_by(4);
-        for i in 0..count {
-            let c = chars.next().unwrap();
-            if c.is_ascii_alphabetic() {
-                stacks.get_mut(i).unwrap().push_back(c);
-            }
+/// Read the stacks diagram, using the numbers along the bottom to tell
+/// how many stacks there are and where they are.
+///
+/// Each crate has to sit over exactly one number, and on top of another
+/// crate (or the floor). Lines don't have to be padded out with spaces
+/// to the full width, and crates can have more than one letter on them,
+/// as long as they still line up.
+pub fn parse_stacks(stacks_layout: &str) -> Result<Stacks, ParseError> {
+    let lines: Vec<&str> = stacks_layout.lines().collect();
+    let (label_line, crate_lines) = lines
+        .split_last()
+        .ok_or_else(|| ParseError::missing(5, "a stacks diagram", stacks_layout))?;
+    let labels = parse_labels(label_line).map_err(|e| e.on_line(lines.len()))?;
+    let mut stacks: Stacks = vec![VecDeque::new(); labels.len()];
+
+    for (n, line) in crate_lines.iter().enumerate() {
+        let error = |e: ParseError| e.on_line(n + 1);
+        let mut filled = vec![false; labels.len()];
+        for (span, label) in parse_crates(line).map_err(error)? {
+            let mut over = labels
+                .iter()
+                .enumerate()
+                .filter(|(_, l)| l.start < span.end && span.start < l.end);
+            let stack = match (over.next(), over.next()) {
+                (Some((stack, _)), None) if !filled[stack] => stack,
+                _ => {
+                    return Err(error(ParseError::new(
+                        5,
+                        ParseErrorKind::Unexpected {
+                            expected: "a crate over one stack number",
+                        },
+                        line,
+                        &line[span],
+                    )))
+                }
+            };
+            filled[stack] = true;
+            stacks[stack].push_back(label.to_string());
+        }
+        // Anything already in a stack is higher up, so it had better
+        // have had something under it.
+        if let Some(stack) = (0..labels.len()).find(|&i| !filled[i] && !stacks[i].is_empty()) {
+            let mut err = ParseError::missing(5, "a crate under the one above", line);
+            err.column = labels[stack].start + 1;
+            return Err(error(err));
         }
     }
 
-    stacks
+    Ok(stacks)
 }
 
-fn parse_moves(moves: &str) -> Moves {
-    let re = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
+fn parse_moves(moves: &str) -> Result<Moves, ParseError> {
+    let re = Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap();
 
-    re.captures_iter(moves)
-        .map(|mov| {
-            (
-                mov.get(1).unwrap().as_str().parse().unwrap(),
-                mov.get(2).unwrap().as_str().parse().unwrap(),
-                mov.get(3).unwrap().as_str().parse().unwrap(),
+    parse_lines(moves, |line| {
+        let mov = re.captures(line).ok_or_else(|| {
+            ParseError::new(
+                5,
+                ParseErrorKind::Unexpected {
+                    expected: "`move N from A to B`",
+                },
+                line,
+                line,
             )
+        })?;
+        Ok((
+            parse_number(5, line, mov.get(1).unwrap().as_str())?,
+            parse_number(5, line, mov.get(2).unwrap().as_str())?,
+            parse_number(5, line, mov.get(3).unwrap().as_str())?,
+        ))
+    })
+}
+
+/// Draw the stacks the same way the puzzle input does, numbers and
+/// all, so that `parse_stacks` reads back exactly the same stacks.
+///
+/// Every column is as wide as the widest crate (or stack number), with
+/// each crate and number centered in its column, and every line is
+/// padded out to the full width.
+pub fn render(stacks: &Stacks) -> String {
+    let numbers: Vec<String> = (1..=stacks.len()).map(|n| n.to_string()).collect();
+    let width = stacks
+        .iter()
+        .flatten()
+        .map(|label| label.chars().count() + 2)
+        .chain(numbers.iter().map(String::len))
+        // No narrower than a one-letter crate, even with no crates.
+        .fold(3, usize::max);
+    let row = |cells: Vec<String>| {
+        cells
+            .iter()
+            .map(|cell| format!("{:^width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let height = stacks.iter().map(VecDeque::len).max().unwrap_or(0);
+    let mut lines: Vec<String> = (0..height)
+        .map(|level| {
+            row(stacks
+                .iter()
+                .map(|stack| match (level + stack.len()).checked_sub(height) {
+                    Some(i) => format!("[{}]", stack[i]),
+                    None => String::new(),
+                })
+                .collect())
         })
-        .collect()
+        .collect();
+    lines.push(row(numbers));
+    lines.join("\n")
+}
+
+/// A move, followed by the diagram of what the stacks look like after
+/// it.
+pub fn show_move(&(n, from, to): &(usize, usize, usize), stacks: &Stacks) -> String {
+    format!("move {} from {} to {}\n{}", n, from, to, render(stacks))
+}
+
+/// Something that can move crates from one stack to another.
+pub trait Crane {
+    /// Move `n` crates off the top of stack `from` and onto stack `to`.
+    /// The stacks count from 0 here.
+    fn move_crates(&self, stacks: &mut Stacks, n: usize, from: usize, to: usize);
+}
+
+/// Take `n` crates off the top of a stack, top first.
+fn lift(stacks: &mut Stacks, n: usize, from: usize) -> VecDeque<Crate> {
+    stacks[from].drain(..n).collect()
 }
 
-fn perform_moves(mut stacks: Stacks, moves: Moves) -> Stacks {
-    for (n, from, to) in moves {
-        for _ in 0..n {
-            let item = stacks.get_mut(from - 1).unwrap().pop_front().unwrap();
-            let to_stack = stacks.get_mut(to - 1).unwrap();
-            to_stack.push_front(item);
+/// Put crates on top of a stack, so the first one ends up on top.
+fn put_down(stacks: &mut Stacks, to: usize, crates: VecDeque<Crate>) {
+    for item in crates.into_iter().rev() {
+        stacks[to].push_front(item);
+    }
+}
+
+/// Moves one crate at a time, so they end up in the reverse order.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn move_crates(&self, stacks: &mut Stacks, n: usize, from: usize, to: usize) {
+        let mut crates = lift(stacks, n, from);
+        crates.make_contiguous().reverse();
+        put_down(stacks, to, crates);
+    }
+}
+
+/// Moves all the crates at once, so they stay in the same order.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn move_crates(&self, stacks: &mut Stacks, n: usize, from: usize, to: usize) {
+        let crates = lift(stacks, n, from);
+        put_down(stacks, to, crates);
+    }
+}
+
+/// Like a CrateMover 9001, but it can only lift `capacity` crates at
+/// once, so bigger moves are done a few crates at a time.
+pub struct LimitedCrane {
+    pub capacity: NonZeroUsize,
+}
+
+impl Crane for LimitedCrane {
+    fn move_crates(&self, stacks: &mut Stacks, n: usize, from: usize, to: usize) {
+        let mut left = n;
+        while left > 0 {
+            let lifted = left.min(self.capacity.get());
+            CrateMover9001.move_crates(stacks, lifted, from, to);
+            left -= lifted;
         }
     }
-    stacks
 }
 
-fn perform_moves_9001(mut stacks: Stacks, moves: Moves) -> Stacks {
-    for (n, from, to) in moves {
-        let mut items = VecDeque::new();
-        for _ in 0..n {
-            items.push_back(stacks.get_mut(from - 1).unwrap().pop_front().unwrap());
+/// Lifts all the crates at once, but the top one slides off on the way
+/// and ends up at the bottom of the ones it was lifted with.
+pub struct RotatingCrane;
+
+impl Crane for RotatingCrane {
+    fn move_crates(&self, stacks: &mut Stacks, n: usize, from: usize, to: usize) {
+        let mut crates = lift(stacks, n, from);
+        crates.rotate_left(n.min(1));
+        put_down(stacks, to, crates);
+    }
+}
+
+/// Why a move can't be made.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MoveErrorKind {
+    /// A stack number that isn't one of the stacks.
+    NoSuchStack(usize),
+    /// Moving crates from a stack back onto itself.
+    SameStack(usize),
+    /// Moving crates from a stack with nothing on it.
+    EmptyStack(usize),
+    NotEnoughCrates {
+        wanted: usize,
+        available: usize,
+    },
+}
+
+/// A move that can't be made, and which move it was (counting from 1).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MoveError {
+    pub number: usize,
+    pub kind: MoveErrorKind,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {}: ", self.number)?;
+        match self.kind {
+            MoveErrorKind::NoSuchStack(stack) => write!(f, "there's no stack {}", stack),
+            MoveErrorKind::SameStack(stack) => {
+                write!(f, "moving from stack {} to itself", stack)
+            }
+            MoveErrorKind::EmptyStack(stack) => write!(f, "stack {} is empty", stack),
+            MoveErrorKind::NotEnoughCrates { wanted, available } => write!(
+                f,
+                "moving {} crates from a stack with {} on it",
+                wanted, available
+            ),
         }
-        let to_stack = stacks.get_mut(to - 1).unwrap();
-        for _ in 0..n {
-            to_stack.push_front(items.pop_back().unwrap());
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+impl From<MoveError> for SolveError {
+    fn from(e: MoveError) -> Self {
+        SolveError::new(5, e.to_string())
+    }
+}
+
+/// Make sure move number `number` can be made on stacks this high.
+fn check_move(
+    heights: &[usize],
+    number: usize,
+    &(n, from, to): &(usize, usize, usize),
+) -> Result<(), MoveError> {
+    let error = |kind| Err(MoveError { number, kind });
+    for stack in [from, to] {
+        if stack == 0 || stack > heights.len() {
+            return error(MoveErrorKind::NoSuchStack(stack));
         }
     }
-    stacks
+    let available = heights[from - 1];
+    if from == to {
+        error(MoveErrorKind::SameStack(from))
+    } else if available == 0 && n > 0 {
+        error(MoveErrorKind::EmptyStack(from))
+    } else if n > available {
+        error(MoveErrorKind::NotEnoughCrates {
+            wanted: n,
+            available,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Check every move without making any of them, so we know before we
+/// start that the whole list can be done. Every crane moves the same
+/// number of crates, so we only need to keep track of how high each
+/// stack is.
+pub fn dry_run(stacks: &Stacks, moves: &Moves) -> Result<(), MoveError> {
+    let mut heights: Vec<usize> = stacks.iter().map(VecDeque::len).collect();
+    for (i, mov) in moves.iter().enumerate() {
+        check_move(&heights, i + 1, mov)?;
+        let &(n, from, to) = mov;
+        heights[from - 1] -= n;
+        heights[to - 1] += n;
+    }
+    Ok(())
+}
+
+/// Make every move with `crane`, calling `trace` after each one. Each
+/// move is checked before it's made, and the first one that can't be
+/// made stops everything.
+pub fn perform_moves_with(
+    crane: &dyn Crane,
+    mut stacks: Stacks,
+    moves: Moves,
+    mut trace: impl FnMut(&(usize, usize, usize), &Stacks),
+) -> Result<Stacks, MoveError> {
+    let mut heights: Vec<usize> = stacks.iter().map(VecDeque::len).collect();
+    for (i, mov) in moves.into_iter().enumerate() {
+        check_move(&heights, i + 1, &mov)?;
+        let (n, from, to) = mov;
+        crane.move_crates(&mut stacks, n, from - 1, to - 1);
+        heights[from - 1] -= n;
+        heights[to - 1] += n;
+        trace(&mov, &stacks);
+    }
+    Ok(stacks)
+}
+
+/// Make every move with a CrateMover 9000. To see each move as it's
+/// made, use [`perform_moves_with`] and [`CrateMover9000`].
+pub fn perform_moves(stacks: Stacks, moves: Moves) -> Result<Stacks, MoveError> {
+    perform_moves_with(&CrateMover9000, stacks, moves, |_, _| {})
+}
+
+/// Make every move with a CrateMover 9001. To see each move as it's
+/// made, use [`perform_moves_with`] and [`CrateMover9001`].
+pub fn perform_moves_9001(stacks: Stacks, moves: Moves) -> Result<Stacks, MoveError> {
+    perform_moves_with(&CrateMover9001, stacks, moves, |_, _| {})
 }
 
-fn stack_tops(stacks: &Stacks) -> String {
-    stacks.iter().map(|stack| stack.front().unwrap()).collect()
+fn stack_tops(stacks: &Stacks) -> Result<String, SolveError> {
+    stacks
+        .iter()
+        .enumerate()
+        .map(|(i, stack)| {
+            stack
+                .front()
+                .map(String::as_str)
+                .ok_or_else(|| SolveError::new(5, format!("stack {} ends up empty", i + 1)))
+        })
+        .collect()
 }
 
 #[aoc_generator(day5)]
-fn parse_input(input: &str) -> (Stacks, Moves) {
-    let (stack_def, move_def) = split_input(input);
-    (parse_stacks(stack_def), parse_moves(move_def))
+fn parse_input(input: &str) -> Result<(Stacks, Moves), ParseError> {
+    let (stack_def, move_def) = split_input(input)?;
+    // The moves start after the diagram and the blank line.
+    let moves_start = stack_def.lines().count() + 1;
+    Ok((
+        parse_stacks(stack_def)?,
+        parse_moves(move_def).map_err(|e| {
+            let line = e.line;
+            e.on_line(line + moves_start)
+        })?,
+    ))
 }
 
 #[aoc(day5, part1)]
-fn find_tops_after_moves((stacks, moves): &(Stacks, Moves)) -> String {
-    stack_tops(&perform_moves(stacks.clone(), moves.clone()))
+fn find_tops_after_moves((stacks, moves): &(Stacks, Moves)) -> Result<String, SolveError> {
+    stack_tops(&perform_moves(stacks.clone(), moves.clone())?)
 }
 
 #[aoc(day5, part2)]
-fn find_tops_after_moves_9001((stacks, moves): &(Stacks, Moves)) -> String {
-    stack_tops(&perform_moves_9001(stacks.clone(), moves.clone()))
+fn find_tops_after_moves_9001((stacks, moves): &(Stacks, Moves)) -> Result<String, SolveError> {
+    stack_tops(&perform_moves_9001(stacks.clone(), moves.clone())?)
 }
 
+pub(crate) static SOLVERS: &[&dyn Solver] = &[
+    &Solution {
+        day: 5,
+        part: 1,
+        name: "find_tops_after_moves",
+        generator: parse_input,
+        solver: |input| Ok(find_tops_after_moves(input)?.into()),
+    },
+    &Solution {
+        day: 5,
+        part: 2,
+        name: "find_tops_after_moves_9001",
+        generator: parse_input,
+        solver: |input| Ok(find_tops_after_moves_9001(input)?.into()),
+    },
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     // Having your editor strip trailing whitespace is usally cool,
     // but not when you have multi-line strings with trailing
-    // whitespace that matters.
+    // whitespace. It doesn't matter to the parser any more, but the
+    // real input has it, so this keeps it.
     const INPUT: &str = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n
 move 1 from 2 to 1
 move 3 from 1 to 3
@@ -115,23 +474,134 @@ move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2",
         );
-        assert_eq!(res, split_input(&INPUT));
+        assert_eq!(res, split_input(INPUT).unwrap());
+    }
+
+    /// A stack with a one-letter crate for each letter, from the top.
+    fn stack(crates: &str) -> VecDeque<Crate> {
+        crates.chars().map(String::from).collect()
     }
 
     #[test]
-    fn test_stack_count() {
-        assert_eq!(3, stack_count(split_input(&INPUT).0));
+    fn test_parse_labels() {
+        assert_eq!(
+            vec![1..2, 5..6, 9..10],
+            parse_labels(" 1   2   3 ").unwrap()
+        );
+        assert_eq!(vec![1..2, 5..6], parse_labels(" 1   2").unwrap());
+
+        let err = parse_labels(" 1   3").unwrap_err();
+        assert_eq!((6, "3"), (err.column, err.text.as_str()));
+        let err = parse_labels("   ").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::Missing { .. }));
     }
 
     #[test]
     fn test_parse_stacks() {
+        assert_eq!(
+            vec![stack("NZ"), stack("DCM"), stack("P"),],
+            parse_stacks(split_input(INPUT).unwrap().0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_stacks_without_padding() {
+        let stripped = INPUT
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(parse_input(INPUT).unwrap(), parse_input(&stripped).unwrap());
+    }
+
+    #[test]
+    fn test_parse_stacks_wide() {
+        let layout = concat!(
+            "                                        [K]\n",
+            "[A] [B] [C] [D] [E] [F] [G] [H] [I] [J] [L]\n",
+            " 1   2   3   4   5   6   7   8   9  10  11",
+        );
+        let stacks = parse_stacks(layout).unwrap();
+        assert_eq!(11, stacks.len());
+        assert_eq!(stack("A"), stacks[0]);
+        assert_eq!(stack("KL"), stacks[10]);
+
+        let layout = " [Foo]\n [Bar] [Baz]\n   1     2";
         assert_eq!(
             vec![
-                VecDeque::from(['N', 'Z']),
-                VecDeque::from(['D', 'C', 'M']),
-                VecDeque::from(['P']),
+                VecDeque::from([String::from("Foo"), String::from("Bar")]),
+                VecDeque::from([String::from("Baz")]),
             ],
-            parse_stacks(split_input(&INPUT).0)
+            parse_stacks(layout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_stacks_errors() {
+        // Over neither stack.
+        let err = parse_stacks("  [A]\n 1   2").unwrap_err();
+        assert_eq!((1, 3, "[A]"), (err.line, err.column, err.text.as_str()));
+
+        // Over both stacks.
+        let err = parse_stacks("[ABCDE]\n 1   2").unwrap_err();
+        assert_eq!((1, 1), (err.line, err.column));
+
+        let err = parse_stacks("[A] B\n 1   2").unwrap_err();
+        assert_eq!((1, 5, "B"), (err.line, err.column, err.text.as_str()));
+
+        let err = parse_stacks("[A]\n[B]\n 1   x").unwrap_err();
+        assert_eq!((3, 6, "x"), (err.line, err.column, err.text.as_str()));
+
+        let err = parse_stacks("").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::Missing { .. }));
+    }
+
+    #[test]
+    fn test_render() {
+        let (stack_def, _) = split_input(INPUT).unwrap();
+        assert_eq!(stack_def, render(&parse_stacks(stack_def).unwrap()));
+
+        let stacks = vec![
+            VecDeque::from([String::from("Foo"), String::from("Bar")]),
+            VecDeque::from([String::from("Baz")]),
+        ];
+        assert_eq!("[Foo]      \n[Bar] [Baz]\n  1     2  ", render(&stacks));
+        assert_eq!(" 1   2 ", render(&vec![VecDeque::new(), VecDeque::new()]));
+    }
+
+    #[test]
+    fn test_perform_moves_traced() {
+        let (stacks, moves) = parse_input(INPUT).unwrap();
+        let mut diagrams = Vec::new();
+        let end = perform_moves_with(
+            &CrateMover9000,
+            stacks.clone(),
+            moves.clone(),
+            |_, stacks| diagrams.push(render(stacks)),
+        )
+        .unwrap();
+        assert_eq!(4, diagrams.len());
+        assert_eq!(
+            "[D]        \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 ",
+            diagrams[0]
+        );
+        assert_eq!(render(&end), diagrams[3]);
+
+        let mut seen = Vec::new();
+        perform_moves_with(&CrateMover9001, stacks.clone(), moves.clone(), |&mov, _| {
+            seen.push(mov)
+        })
+        .unwrap();
+        assert_eq!(moves, seen);
+
+        let mut trace = Vec::new();
+        perform_moves_with(&CrateMover9000, stacks, moves, |mov, stacks| {
+            trace.push(show_move(mov, stacks))
+        })
+        .unwrap();
+        assert_eq!(
+            "move 1 from 2 to 1\n[D]        \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 ",
+            trace[0]
         );
     }
 
@@ -139,32 +609,130 @@ move 1 from 1 to 2",
     fn test_parse_moves() {
         assert_eq!(
             vec![(1, 2, 1), (3, 1, 3), (2, 2, 1), (1, 1, 2)],
-            parse_moves(split_input(&INPUT).1)
+            parse_moves(split_input(INPUT).unwrap().1).unwrap()
         );
     }
 
+    #[test]
+    fn test_parse_input_errors() {
+        let err = parse_input("[A]\n 1 \nmove 1 from 1 to 1").unwrap_err();
+        assert_eq!((5, 3, 19), (err.day, err.line, err.column));
+
+        // B has nothing under it.
+        let err = parse_input("[A] [B]\n[C]\n 1   2 \n\nmove 1 from 1 to 2").unwrap_err();
+        assert_eq!((2, 6), (err.line, err.column));
+
+        let err = parse_input("[A]\n 1 \n\nmove 1 from 1 to 1\nmove one from 1 to 1").unwrap_err();
+        assert_eq!((5, 1), (err.line, err.column));
+        assert_eq!("move one from 1 to 1", err.text);
+
+        let err = parse_input("[A]\n 1 \n\nmove 99999999999999999999 from 1 to 1").unwrap_err();
+        assert_eq!((4, 6), (err.line, err.column));
+        assert_eq!(ParseErrorKind::InvalidNumber, err.kind);
+    }
+
     #[test]
     fn test_perform_moves() {
-        let (stack_def, move_def) = split_input(&INPUT);
+        let (stack_def, move_def) = split_input(INPUT).unwrap();
         assert_eq!(
-            vec![
-                VecDeque::from(['C']),
-                VecDeque::from(['M']),
-                VecDeque::from(['Z', 'N', 'D', 'P']),
-            ],
-            perform_moves(parse_stacks(stack_def), parse_moves(move_def))
+            vec![stack("C"), stack("M"), stack("ZNDP"),],
+            perform_moves(
+                parse_stacks(stack_def).unwrap(),
+                parse_moves(move_def).unwrap()
+            )
+            .unwrap()
         );
     }
 
+    #[test]
+    fn test_cranes() {
+        let move_four = |crane: &dyn Crane| {
+            let stacks = vec![stack("ABCDE"), stack("")];
+            perform_moves_with(crane, stacks, vec![(4, 1, 2)], |_, _| {}).unwrap()
+        };
+        assert_eq!(vec![stack("E"), stack("DCBA")], move_four(&CrateMover9000));
+        assert_eq!(vec![stack("E"), stack("ABCD")], move_four(&CrateMover9001));
+        assert_eq!(
+            vec![stack("E"), stack("CDAB")],
+            move_four(&LimitedCrane {
+                capacity: NonZeroUsize::new(2).unwrap()
+            })
+        );
+        assert_eq!(
+            vec![stack("E"), stack("DABC")],
+            move_four(&LimitedCrane {
+                capacity: NonZeroUsize::new(3).unwrap()
+            })
+        );
+        assert_eq!(vec![stack("E"), stack("BCDA")], move_four(&RotatingCrane));
+
+        // A crane that lifts one crate at a time is a CrateMover 9000.
+        let (stacks, moves) = parse_input(INPUT).unwrap();
+        assert_eq!(
+            perform_moves(stacks.clone(), moves.clone()),
+            perform_moves_with(
+                &LimitedCrane {
+                    capacity: NonZeroUsize::new(1).unwrap()
+                },
+                stacks,
+                moves,
+                |_, _| {}
+            )
+        );
+    }
+
+    #[test]
+    fn test_move_errors() {
+        let (stacks, _) = parse_input(INPUT).unwrap();
+        let check = |moves: Moves| {
+            let dry = dry_run(&stacks, &moves).map(|_| ());
+            let wet = perform_moves(stacks.clone(), moves).map(|_| ());
+            assert_eq!(dry, wet);
+            dry.unwrap_err()
+        };
+
+        assert_eq!(
+            MoveError {
+                number: 2,
+                kind: MoveErrorKind::NoSuchStack(0)
+            },
+            check(vec![(1, 2, 1), (1, 0, 1)])
+        );
+        assert_eq!(MoveErrorKind::NoSuchStack(4), check(vec![(1, 2, 4)]).kind);
+        assert_eq!(MoveErrorKind::SameStack(2), check(vec![(1, 2, 2)]).kind);
+        assert_eq!(
+            MoveErrorKind::EmptyStack(3),
+            check(vec![(1, 3, 1), (1, 3, 1)]).kind
+        );
+        let err = check(vec![(1, 2, 1), (3, 2, 1)]);
+        assert_eq!(
+            MoveErrorKind::NotEnoughCrates {
+                wanted: 3,
+                available: 2
+            },
+            err.kind
+        );
+        assert_eq!(
+            "move 2: moving 3 crates from a stack with 2 on it",
+            err.to_string()
+        );
+
+        let err = crate::find(5, 1, None)
+            .unwrap()
+            .solve("[A]\n 1 \n\nmove 2 from 1 to 2")
+            .unwrap_err();
+        assert_eq!("day 5: move 1: there's no stack 2", err.to_string());
+    }
+
     #[test]
     fn test_stack_tops() {
         assert_eq!(
             String::from("CMZ"),
-            stack_tops(&vec![
-                VecDeque::from(['C']),
-                VecDeque::from(['M']),
-                VecDeque::from(['Z', 'N', 'D', 'P']),
-            ])
+            stack_tops(&vec![stack("C"), stack("M"), stack("ZNDP"),]).unwrap()
+        );
+        assert_eq!(
+            SolveError::new(5, "stack 2 ends up empty"),
+            stack_tops(&vec![stack("C"), stack(""), stack("ZNDP"),]).unwrap_err()
         );
     }
 
@@ -173,26 +741,23 @@ move 1 from 1 to 2",
         assert_eq!(
             String::from("CMZ"),
             find_tops_after_moves(&(
-                vec![
-                    VecDeque::from(['N', 'Z']),
-                    VecDeque::from(['D', 'C', 'M']),
-                    VecDeque::from(['P']),
-                ],
+                vec![stack("NZ"), stack("DCM"), stack("P"),],
                 vec![(1, 2, 1), (3, 1, 3), (2, 2, 1), (1, 1, 2)]
             ))
+            .unwrap()
         );
     }
 
     #[test]
     fn test_perform_moves_9001() {
-        let (stack_def, move_def) = split_input(&INPUT);
+        let (stack_def, move_def) = split_input(INPUT).unwrap();
         assert_eq!(
-            vec![
-                VecDeque::from(['M']),
-                VecDeque::from(['C']),
-                VecDeque::from(['D', 'N', 'Z', 'P']),
-            ],
-            perform_moves_9001(parse_stacks(stack_def), parse_moves(move_def))
+            vec![stack("M"), stack("C"), stack("DNZP"),],
+            perform_moves_9001(
+                parse_stacks(stack_def).unwrap(),
+                parse_moves(move_def).unwrap()
+            )
+            .unwrap()
         );
     }
 
@@ -201,13 +766,85 @@ move 1 from 1 to 2",
         assert_eq!(
             String::from("MCD"),
             find_tops_after_moves_9001(&(
-                vec![
-                    VecDeque::from(['N', 'Z']),
-                    VecDeque::from(['D', 'C', 'M']),
-                    VecDeque::from(['P']),
-                ],
+                vec![stack("NZ"), stack("DCM"), stack("P"),],
                 vec![(1, 2, 1), (3, 1, 3), (2, 2, 1), (1, 1, 2)]
             ))
+            .unwrap()
+        );
+    }
+
+    fn stacks() -> impl Strategy<Value = Stacks> {
+        prop::collection::vec(
+            prop::collection::vec(prop::char::range('A', 'Z').prop_map(String::from), 0..8)
+                .prop_map(VecDeque::from),
+            1..=9,
+        )
+    }
+
+    /// Turn arbitrary numbers into moves that can actually be made on
+    /// `stacks`, one after the other.
+    fn valid_moves(stacks: &Stacks, raw: &[(usize, usize, usize)]) -> Moves {
+        let mut heights: Vec<usize> = stacks.iter().map(VecDeque::len).collect();
+        raw.iter()
+            .filter_map(|&(n, from, to)| {
+                let (from, to) = (from % heights.len(), to % heights.len());
+                if heights[from] == 0 || from == to {
+                    return None;
+                }
+                let n = 1 + n % heights[from];
+                heights[from] -= n;
+                heights[to] += n;
+                Some((n, from + 1, to + 1))
+            })
+            .collect()
+    }
+
+    fn render_input(stacks: &Stacks, moves: &Moves) -> String {
+        let mut lines = vec![render(stacks), String::new()];
+        lines.extend(
+            moves
+                .iter()
+                .map(|(n, from, to)| format!("move {} from {} to {}", n, from, to)),
         );
+        lines.join("\n")
+    }
+
+    fn crate_count(stacks: &Stacks) -> usize {
+        stacks.iter().map(VecDeque::len).sum()
+    }
+
+    proptest! {
+        #[test]
+        fn prop_moves_preserve_crate_count(
+            stacks in stacks(),
+            raw in prop::collection::vec((0..10usize, 0..9usize, 0..9usize), 0..30)
+        ) {
+            let moves = valid_moves(&stacks, &raw);
+            let count = crate_count(&stacks);
+            prop_assert_eq!(Ok(()), dry_run(&stacks, &moves));
+            let after = perform_moves(stacks.clone(), moves.clone()).unwrap();
+            prop_assert_eq!(count, crate_count(&after));
+            prop_assert_eq!(count, crate_count(&perform_moves_9001(stacks, moves).unwrap()));
+        }
+
+        #[test]
+        fn prop_render_round_trip(
+            stacks in prop::collection::vec(
+                prop::collection::vec("[A-Za-z]{1,4}", 0..6).prop_map(VecDeque::from),
+                1..=12,
+            )
+        ) {
+            prop_assert_eq!(&stacks, &parse_stacks(&render(&stacks)).unwrap());
+        }
+
+        #[test]
+        fn prop_parse_input_round_trip(
+            stacks in stacks(),
+            raw in prop::collection::vec((0..10usize, 0..9usize, 0..9usize), 1..30)
+        ) {
+            let moves = valid_moves(&stacks, &raw);
+            prop_assume!(!moves.is_empty());
+            prop_assert_eq!((stacks.clone(), moves.clone()), parse_input(&render_input(&stacks, &moves)).unwrap());
+        }
     }
 }
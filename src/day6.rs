@@ -1,3 +1,5 @@
+use crate::error::SolveError;
+use crate::registry::{Answer, Solution, Solver};
 use aoc_runner_derive::aoc;
 use std::collections::VecDeque;
 
@@ -12,7 +14,9 @@ use std::collections::VecDeque;
 /// full, then we're at the end of the marker, and the next character
 /// is the position we're looking for. If we need to keep going, we
 /// push the character onto the buffer and move ahead.
-fn find_start(stream: &str, marker_length: usize) -> usize {
+///
+/// If the stream ends before we find a marker, there's no start.
+fn find_start(stream: &str, marker_length: usize) -> Option<usize> {
     let mut buf = VecDeque::with_capacity(marker_length);
     for (i, c) in stream.chars().enumerate() {
         let mut dup_i = 0;
@@ -28,24 +32,49 @@ fn find_start(stream: &str, marker_length: usize) -> usize {
             for _ in 0..dup_i {
                 buf.pop_front();
             }
-        } else if dup_i == marker_length - 1 {
-            return i + 1;
+        } else if dup_i + 1 == marker_length {
+            return Some(i + 1);
         }
         buf.push_back(c);
     }
-    panic!("No marker found!")
+    None
 }
 
 #[aoc(day6, part1)]
-fn find_start_of_packet(stream: &str) -> usize {
+fn find_start_of_packet(stream: &str) -> Option<usize> {
     find_start(stream, 4)
 }
 
 #[aoc(day6, part2)]
-fn find_start_of_message(stream: &str) -> usize {
+fn find_start_of_message(stream: &str) -> Option<usize> {
     find_start(stream, 14)
 }
 
+pub(crate) static SOLVERS: &[&dyn Solver] = &[
+    &Solution {
+        day: 6,
+        part: 1,
+        name: "find_start_of_packet",
+        generator: |stream| Ok(stream.to_string()),
+        solver: |stream| {
+            find_start_of_packet(stream)
+                .map(Answer::from)
+                .ok_or_else(|| SolveError::new(6, "no start-of-packet marker"))
+        },
+    },
+    &Solution {
+        day: 6,
+        part: 2,
+        name: "find_start_of_message",
+        generator: |stream| Ok(stream.to_string()),
+        solver: |stream| {
+            find_start_of_message(stream)
+                .map(Answer::from)
+                .ok_or_else(|| SolveError::new(6, "no start-of-message marker"))
+        },
+    },
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,20 +88,27 @@ mod tests {
 
     #[test]
     fn test_find_start_of_packet() {
-        assert_eq!(7, find_start_of_packet(&INPUT1));
-        assert_eq!(5, find_start_of_packet(&INPUT2));
-        assert_eq!(6, find_start_of_packet(&INPUT3));
-        assert_eq!(10, find_start_of_packet(&INPUT4));
-        assert_eq!(11, find_start_of_packet(&INPUT5));
-        assert_eq!(12, find_start_of_packet(&INPUT6));
+        assert_eq!(Some(7), find_start_of_packet(INPUT1));
+        assert_eq!(Some(5), find_start_of_packet(INPUT2));
+        assert_eq!(Some(6), find_start_of_packet(INPUT3));
+        assert_eq!(Some(10), find_start_of_packet(INPUT4));
+        assert_eq!(Some(11), find_start_of_packet(INPUT5));
+        assert_eq!(Some(12), find_start_of_packet(INPUT6));
     }
 
     #[test]
     fn test_find_start_of_message() {
-        assert_eq!(19, find_start_of_message(&INPUT1));
-        assert_eq!(23, find_start_of_message(&INPUT2));
-        assert_eq!(23, find_start_of_message(&INPUT3));
-        assert_eq!(29, find_start_of_message(&INPUT4));
-        assert_eq!(26, find_start_of_message(&INPUT5));
+        assert_eq!(Some(19), find_start_of_message(INPUT1));
+        assert_eq!(Some(23), find_start_of_message(INPUT2));
+        assert_eq!(Some(23), find_start_of_message(INPUT3));
+        assert_eq!(Some(29), find_start_of_message(INPUT4));
+        assert_eq!(Some(26), find_start_of_message(INPUT5));
+    }
+
+    #[test]
+    fn test_no_marker() {
+        assert_eq!(None, find_start_of_packet("abcabcabc"));
+        assert_eq!(None, find_start_of_message(INPUT1.get(..10).unwrap()));
+        assert_eq!(None, find_start_of_packet(""));
     }
 }
@@ -1,28 +1,83 @@
+use crate::error::{parse_number, ParseError, ParseErrorKind, SolveError};
+use crate::registry::{Answer, Solution, Solver};
+use aoc_runner_derive::{aoc, aoc_generator};
 use either::{Either, Left, Right};
 
-type DiskObject<'a> = Either<File<'a>, Dir<'a>>;
+type DiskObject = Either<File, Dir>;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-struct File<'a> {
-    name: &'a str,
+#[derive(Clone, Debug, PartialEq)]
+struct File {
+    name: String,
     bytes: usize,
 }
 
 #[derive(Clone, Debug, PartialEq)]
-struct Dir<'a> {
-    name: &'a str,
-    contents: Vec<DiskObject<'a>>,
+struct Dir {
+    name: String,
+    contents: Vec<DiskObject>,
+}
+
+impl Dir {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            contents: Vec::new(),
+        }
+    }
+
+    /// Find the index of the subdirectory `name`, adding an empty one
+    /// if this is the first we've heard of it.
+    fn subdir_index(&mut self, name: &str) -> usize {
+        let found = self
+            .contents
+            .iter()
+            .position(|obj| matches!(obj, Right(dir) if dir.name == name));
+        found.unwrap_or_else(|| {
+            self.contents.push(Right(Dir::new(name)));
+            self.contents.len() - 1
+        })
+    }
+
+    /// Add a file, or update its size if a listing already had it, so
+    /// that listing a directory twice doesn't count its files twice.
+    fn add_file(&mut self, name: &str, bytes: usize) {
+        let found = self.contents.iter_mut().find_map(|obj| match obj {
+            Left(file) if file.name == name => Some(file),
+            _ => None,
+        });
+        match found {
+            Some(file) => file.bytes = bytes,
+            None => self.contents.push(Left(File {
+                name: name.to_string(),
+                bytes,
+            })),
+        }
+    }
+
+    fn descend(&mut self, path: &[usize]) -> &mut Dir {
+        path.iter()
+            .fold(self, |dir, &i| match &mut dir.contents[i] {
+                Right(sub) => sub,
+                Left(_) => unreachable!("path only ever points at directories"),
+            })
+    }
 }
 
 trait MadeOfBytes {
     fn size(&self) -> usize;
 }
 
-impl MadeOfBytes for DiskObject<'_> {
+impl MadeOfBytes for DiskObject {
     fn size(&self) -> usize {
         match self {
             Left(f) => f.bytes,
-            Right(dir) => dir.contents.iter().map(MadeOfBytes::size).sum(),
+            // A transcript can list more bytes than fit in a usize, but
+            // a disk that full is just as much too full to matter.
+            Right(dir) => dir
+                .contents
+                .iter()
+                .map(MadeOfBytes::size)
+                .fold(0, usize::saturating_add),
         }
     }
 }
@@ -31,10 +86,10 @@ trait Walkable {
     fn walk(&self) -> Vec<DiskObject>;
 }
 
-impl Walkable for DiskObject<'_> {
+impl Walkable for DiskObject {
     fn walk(&self) -> Vec<DiskObject> {
         match self {
-            Left(f) => vec![Left(*f)],
+            Left(f) => vec![Left(f.clone())],
             Right(dir) => {
                 let mut contents: Vec<DiskObject> =
                     dir.contents.iter().flat_map(Walkable::walk).collect();
@@ -46,63 +101,182 @@ impl Walkable for DiskObject<'_> {
     }
 }
 
+/// Rebuild the filesystem from a terminal transcript of `cd` and
+/// `ls` commands.
+///
+/// We keep the root directory and a path of indices from the root to
+/// the current directory. `cd` pushes onto (or pops off of) the path,
+/// and the lines of `ls` output get added to whatever directory the
+/// path points at (listing a directory again doesn't add anything new
+/// to it). A directory we `cd` into without having seen it in
+/// a listing gets created on the spot.
+#[aoc_generator(day7)]
+fn parse_transcript(input: &str) -> Result<DiskObject, ParseError> {
+    let mut root = Dir::new("");
+    let mut path: Vec<usize> = Vec::new();
+
+    for (n, line) in input.lines().enumerate() {
+        let unexpected = || {
+            ParseError::new(
+                7,
+                ParseErrorKind::Unexpected {
+                    expected: "a command or a directory listing",
+                },
+                line,
+                line,
+            )
+            .on_line(n + 1)
+        };
+        let cwd = root.descend(&path);
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["$", "cd", "/"] => path.clear(),
+            ["$", "cd", ".."] => {
+                path.pop();
+            }
+            ["$", "cd", name] => path.push(cwd.subdir_index(name)),
+            ["$", "ls"] => {}
+            ["dir", name] => {
+                cwd.subdir_index(name);
+            }
+            // Any other command, so it isn't mistaken for a file.
+            ["$", ..] => return Err(unexpected()),
+            [bytes, name] => cwd.add_file(
+                name,
+                parse_number(7, line, bytes).map_err(|e| e.on_line(n + 1))?,
+            ),
+            _ => return Err(unexpected()),
+        }
+    }
+
+    Ok(Right(root))
+}
+
+fn dir_sizes(disk: &DiskObject) -> impl Iterator<Item = usize> {
+    disk.walk()
+        .into_iter()
+        .filter(|obj| obj.is_right())
+        .map(|obj| obj.size())
+}
+
+#[aoc(day7, part1)]
+fn small_dirs_total(disk: &DiskObject) -> usize {
+    dir_sizes(disk).filter(|size| size < &100000).sum()
+}
+
+/// Find the smallest directory that frees up enough space for the
+/// update once it's deleted. If there's already enough free space, we
+/// don't need to delete anything, but the smallest directory will
+/// still do. If the disk holds more than it can fit, nothing will.
+#[aoc(day7, part2)]
+fn smallest_dir_to_delete(disk: &DiskObject) -> Option<usize> {
+    let free = 70000000_usize.checked_sub(disk.size())?;
+    let needed = 30000000_usize.saturating_sub(free);
+    dir_sizes(disk).filter(|size| size >= &needed).min()
+}
+
+pub(crate) static SOLVERS: &[&dyn Solver] = &[
+    &Solution {
+        day: 7,
+        part: 1,
+        name: "small_dirs_total",
+        generator: parse_transcript,
+        solver: |disk| Ok(small_dirs_total(disk).into()),
+    },
+    &Solution {
+        day: 7,
+        part: 2,
+        name: "smallest_dir_to_delete",
+        generator: parse_transcript,
+        solver: |disk| {
+            smallest_dir_to_delete(disk)
+                .map(Answer::from)
+                .ok_or_else(|| SolveError::new(7, "the disk holds more than it can fit"))
+        },
+    },
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    const INPUT: &str = "$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k";
 
-    fn example_disk<'a>() -> DiskObject<'a> {
+    fn example_disk() -> DiskObject {
         Right(Dir {
-            name: "",
+            name: String::new(),
             contents: vec![
                 Right(Dir {
-                    name: "a",
+                    name: String::from("a"),
                     contents: vec![
                         Right(Dir {
-                            name: "e",
+                            name: String::from("e"),
                             contents: vec![Left(File {
-                                name: "i",
+                                name: String::from("i"),
                                 bytes: 584,
                             })],
                         }),
                         Left(File {
-                            name: "f",
+                            name: String::from("f"),
                             bytes: 29116,
                         }),
                         Left(File {
-                            name: "g",
+                            name: String::from("g"),
                             bytes: 2557,
                         }),
                         Left(File {
-                            name: "h.lst",
+                            name: String::from("h.lst"),
                             bytes: 62596,
                         }),
                     ],
                 }),
                 Left(File {
-                    name: "b.txt",
+                    name: String::from("b.txt"),
                     bytes: 14848514,
                 }),
                 Left(File {
-                    name: "c.dat",
+                    name: String::from("c.dat"),
                     bytes: 8504156,
                 }),
                 Right(Dir {
-                    name: "d",
+                    name: String::from("d"),
                     contents: vec![
                         Left(File {
-                            name: "j",
+                            name: String::from("j"),
                             bytes: 4060174,
                         }),
                         Left(File {
-                            name: "d.log",
+                            name: String::from("d.log"),
                             bytes: 8033020,
                         }),
                         Left(File {
-                            name: "d.ext",
+                            name: String::from("d.ext"),
                             bytes: 5626152,
                         }),
                         Left(File {
-                            name: "k",
+                            name: String::from("k"),
                             bytes: 7214296,
                         }),
                     ],
@@ -116,7 +290,7 @@ mod tests {
         assert_eq!(
             42,
             Left(File {
-                name: "a",
+                name: String::from("a"),
                 bytes: 42
             })
             .size()
@@ -137,4 +311,151 @@ mod tests {
                 .sum()
         );
     }
+
+    #[test]
+    fn test_parse_transcript() {
+        assert_eq!(example_disk(), parse_transcript(INPUT).unwrap());
+    }
+
+    #[test]
+    fn test_parse_transcript_cd_root() {
+        let disk = parse_transcript("$ cd a\n$ cd b\n1 x\n$ cd /\n$ cd c\n2 y").unwrap();
+        assert_eq!(
+            Right(Dir {
+                name: String::new(),
+                contents: vec![
+                    Right(Dir {
+                        name: String::from("a"),
+                        contents: vec![Right(Dir {
+                            name: String::from("b"),
+                            contents: vec![Left(File {
+                                name: String::from("x"),
+                                bytes: 1
+                            })]
+                        })]
+                    }),
+                    Right(Dir {
+                        name: String::from("c"),
+                        contents: vec![Left(File {
+                            name: String::from("y"),
+                            bytes: 2
+                        })]
+                    }),
+                ]
+            }),
+            disk
+        );
+    }
+
+    #[test]
+    fn test_parse_transcript_errors() {
+        let err = parse_transcript("$ cd /\n$ ls\n12x b.txt").unwrap_err();
+        assert_eq!((7, 3, 1), (err.day, err.line, err.column));
+        assert_eq!("12x", err.text);
+
+        let err = parse_transcript("$ cd /\n$ rm -rf b.txt").unwrap_err();
+        assert_eq!(2, err.line);
+
+        let err = parse_transcript("$ cd /\n$ ls\n$ cd").unwrap_err();
+        assert_eq!(
+            (
+                3,
+                ParseErrorKind::Unexpected {
+                    expected: "a command or a directory listing"
+                }
+            ),
+            (err.line, err.kind)
+        );
+    }
+
+    #[test]
+    fn test_parse_transcript_repeated_ls() {
+        let disk =
+            parse_transcript("$ cd /\n$ ls\n100 a\ndir b\n$ cd /\n$ ls\n100 a\ndir b").unwrap();
+        assert_eq!(100, disk.size());
+        assert_eq!(100, small_dirs_total(&disk));
+        assert_eq!(3, disk.walk().len());
+    }
+
+    #[test]
+    fn test_small_dirs_total() {
+        assert_eq!(95437, small_dirs_total(&parse_transcript(INPUT).unwrap()));
+    }
+
+    #[test]
+    fn test_smallest_dir_to_delete() {
+        assert_eq!(
+            Some(24933642),
+            smallest_dir_to_delete(&parse_transcript(INPUT).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_smallest_dir_to_delete_small_disk() {
+        let disk = parse_transcript("$ cd /\n$ ls\n1 x").unwrap();
+        assert_eq!(Some(1), smallest_dir_to_delete(&disk));
+
+        let disk = parse_transcript("$ cd /\n$ ls\n70000001 x").unwrap();
+        assert_eq!(None, smallest_dir_to_delete(&disk));
+    }
+
+    fn files() -> impl Strategy<Value = Vec<usize>> {
+        prop::collection::vec(1..1_000_000usize, 0..4)
+    }
+
+    /// A directory whose files are called `f0`, `f1`, ... and whose
+    /// subdirectories are called `d0`, `d1`, ...
+    fn dir() -> impl Strategy<Value = Dir> {
+        files()
+            .prop_map(|files| build(files, vec![]))
+            .prop_recursive(4, 32, 4, |inner| {
+                (files(), prop::collection::vec(inner, 0..4))
+                    .prop_map(|(files, dirs)| build(files, dirs))
+            })
+    }
+
+    fn build(files: Vec<usize>, dirs: Vec<Dir>) -> Dir {
+        let files = files.into_iter().enumerate().map(|(i, bytes)| {
+            Left(File {
+                name: format!("f{}", i),
+                bytes,
+            })
+        });
+        let dirs = dirs.into_iter().enumerate().map(|(i, dir)| {
+            Right(Dir {
+                name: format!("d{}", i),
+                ..dir
+            })
+        });
+        Dir {
+            name: String::new(),
+            contents: dirs.chain(files).collect(),
+        }
+    }
+
+    fn transcript(dir: &Dir, lines: &mut Vec<String>) {
+        lines.push(String::from("$ ls"));
+        for obj in &dir.contents {
+            match obj {
+                Left(f) => lines.push(format!("{} {}", f.bytes, f.name)),
+                Right(d) => lines.push(format!("dir {}", d.name)),
+            }
+        }
+        for obj in &dir.contents {
+            if let Right(d) = obj {
+                lines.push(format!("$ cd {}", d.name));
+                transcript(d, lines);
+                lines.push(String::from("$ cd .."));
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_transcript_round_trip(root in dir()) {
+            let mut lines = vec![String::from("$ cd /")];
+            transcript(&root, &mut lines);
+            prop_assert_eq!(Right(root), parse_transcript(&lines.join("\n")).unwrap());
+        }
+    }
 }
@@ -0,0 +1,204 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// What was wrong with the bit of input a [`ParseError`] points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Text that isn't any of the things that are allowed there.
+    Unexpected { expected: &'static str },
+    /// Text that should have been a number but isn't one.
+    InvalidNumber,
+    /// The line (or the input) stopped before something we needed.
+    Missing { expected: &'static str },
+    /// A number that's fine on its own, but adds up to more than we
+    /// can hold.
+    Overflow,
+}
+
+/// A piece of puzzle input that couldn't be parsed, along with where
+/// it was. Lines and columns both count from 1, the same way an
+/// editor would show them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    /// An error about `text`, which has to be a slice of `line` (that's
+    /// how we work out the column). The error is placed on line 1;
+    /// use [`ParseError::on_line`] once the real line number is known.
+    pub fn new(day: u8, kind: ParseErrorKind, line: &str, text: &str) -> Self {
+        let offset = (text.as_ptr() as usize)
+            .checked_sub(line.as_ptr() as usize)
+            .filter(|&offset| offset <= line.len())
+            .unwrap_or(0);
+        Self {
+            day,
+            line: 1,
+            column: line[..offset].chars().count() + 1,
+            text: text.to_string(),
+            kind,
+        }
+    }
+
+    /// An error for something that should have come after the end of
+    /// `line` but didn't.
+    pub fn missing(day: u8, expected: &'static str, line: &str) -> Self {
+        Self::new(
+            day,
+            ParseErrorKind::Missing { expected },
+            line,
+            &line[line.len()..],
+        )
+    }
+
+    /// Move an error found while parsing a single line onto line
+    /// number `line` of the whole input.
+    pub fn on_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    /// Shift an error found while parsing part of a line over by
+    /// `columns`, for when the part didn't start at the beginning.
+    pub fn shifted(mut self, columns: usize) -> Self {
+        self.column += columns;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {}, line {}, column {}: ",
+            self.day, self.line, self.column
+        )?;
+        match self.kind {
+            ParseErrorKind::Unexpected { expected } => {
+                write!(f, "expected {}, found `{}`", expected, self.text)
+            }
+            ParseErrorKind::InvalidNumber => write!(f, "invalid number `{}`", self.text),
+            ParseErrorKind::Missing { expected } => write!(f, "missing {}", expected),
+            ParseErrorKind::Overflow => write!(f, "`{}` makes the total too large", self.text),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Input that parsed fine but that a puzzle has no answer for, like a
+/// datastream without a marker in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveError {
+    pub day: u8,
+    pub reason: String,
+}
+
+impl SolveError {
+    pub fn new(day: u8, reason: impl Into<String>) -> Self {
+        Self {
+            day,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "day {}: {}", self.day, self.reason)
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// Anything that can go wrong going from puzzle input to an answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    Parse(ParseError),
+    Solve(SolveError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "{}", e),
+            Error::Solve(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<SolveError> for Error {
+    fn from(e: SolveError) -> Self {
+        Error::Solve(e)
+    }
+}
+
+/// Parse `text`, a slice of `line`, as a number.
+pub(crate) fn parse_number<T: FromStr>(day: u8, line: &str, text: &str) -> Result<T, ParseError> {
+    text.parse()
+        .map_err(|_| ParseError::new(day, ParseErrorKind::InvalidNumber, line, text))
+}
+
+/// Parse every line of `input` with `parse_line`, stopping at the first
+/// error and giving it the right line number.
+pub(crate) fn parse_lines<'a, T>(
+    input: &'a str,
+    parse_line: impl Fn(&'a str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse_line(line).map_err(|e| e.on_line(i + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_from_slice() {
+        let line = "move 1 from x to 3";
+        let err = parse_number::<usize>(5, line, &line[12..13]).unwrap_err();
+        assert_eq!(
+            ParseError {
+                day: 5,
+                line: 1,
+                column: 13,
+                text: String::from("x"),
+                kind: ParseErrorKind::InvalidNumber,
+            },
+            err
+        );
+    }
+
+    #[test]
+    fn test_missing() {
+        let err = ParseError::missing(2, "a second column", "A");
+        assert_eq!(2, err.column);
+        assert_eq!("", err.text);
+    }
+
+    #[test]
+    fn test_parse_lines_numbers_lines() {
+        let err = parse_lines("1\n2\nthree\n4", |l| parse_number::<u32>(1, l, l)).unwrap_err();
+        assert_eq!((3, 1), (err.line, err.column));
+        assert_eq!(
+            "day 1, line 3, column 1: invalid number `three`",
+            err.to_string()
+        );
+    }
+}
@@ -0,0 +1,593 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Values that come one after another with nothing in between, like
+/// integers, so that an interval of them can be counted and two
+/// intervals can sit right next to each other.
+pub trait Discrete: Copy + Ord {
+    const MIN: Self;
+    const MAX: Self;
+
+    /// The next value up, unless this is the biggest one.
+    fn succ(self) -> Option<Self>;
+
+    /// The next value down, unless this is the smallest one.
+    fn pred(self) -> Option<Self>;
+
+    /// How many values there are from `self` up to `end`, both
+    /// included. `end` can't be smaller than `self`.
+    fn count_to(self, end: Self) -> u128;
+}
+
+macro_rules! discrete {
+    ($($t:ty),*) => {
+        $(
+            impl Discrete for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn succ(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn pred(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+
+                fn count_to(self, end: Self) -> u128 {
+                    (end as i128 - self as i128) as u128 + 1
+                }
+            }
+        )*
+    };
+}
+
+discrete!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// All the values from `start` to `end`, both included. There's always
+/// at least one.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Discrete> Interval<T> {
+    /// `None` if `end` comes before `start`.
+    pub fn new(start: T, end: T) -> Option<Self> {
+        (start <= end).then_some(Self { start, end })
+    }
+
+    pub fn point(value: T) -> Self {
+        Self {
+            start: value,
+            end: value,
+        }
+    }
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    /// How many values are in the interval. There's no `is_empty`,
+    /// since it never is.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u128 {
+        self.start.count_to(self.end)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    /// Whether every value in `other` is also in this interval.
+    pub fn contains_interval(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// Whether the two intervals don't overlap, but there's nothing
+    /// between them either.
+    pub fn is_adjacent(&self, other: &Self) -> bool {
+        self.end.succ() == Some(other.start) || other.end.succ() == Some(self.start)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        Self::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    /// The values in either interval, as long as that's still one
+    /// interval (they overlap or they're adjacent).
+    pub fn union(&self, other: &Self) -> Option<Self> {
+        if self.overlaps(other) || self.is_adjacent(other) {
+            Some(Self {
+                start: self.start.min(other.start),
+                end: self.end.max(other.end),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The values in this interval but not in `other`. That can be
+    /// nothing, one interval, or two if `other` is in the middle.
+    pub fn difference(&self, other: &Self) -> Vec<Self> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+        let before = other
+            .start
+            .pred()
+            .and_then(|end| Self::new(self.start, end));
+        let after = other
+            .end
+            .succ()
+            .and_then(|start| Self::new(start, self.end));
+        before.into_iter().chain(after).collect()
+    }
+}
+
+impl<T: Discrete> From<Interval<T>> for RangeInclusive<T> {
+    fn from(interval: Interval<T>) -> Self {
+        interval.start..=interval.end
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// A set of values, kept as a sorted list of intervals that neither
+/// overlap nor touch, so there's only one way to write any given set.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self {
+            intervals: Vec::new(),
+        }
+    }
+}
+
+impl<T: Discrete> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add every value in `interval`, merging it with any intervals
+    /// that it overlaps or touches.
+    pub fn insert(&mut self, interval: Interval<T>) {
+        // Everything before `first` ends too early to merge with, and
+        // everything from `last` on starts too late.
+        let first = self
+            .intervals
+            .partition_point(|i| i.end < interval.start && !i.is_adjacent(&interval));
+        let last = self
+            .intervals
+            .partition_point(|i| i.start <= interval.end || i.is_adjacent(&interval));
+        let merged = self.intervals[first..last]
+            .iter()
+            .fold(interval, |merged, i| merged.union(i).unwrap());
+        self.intervals.splice(first..last, [merged]);
+    }
+
+    /// Take every value in `interval` out of the set.
+    pub fn remove(&mut self, interval: Interval<T>) {
+        let first = self.intervals.partition_point(|i| i.end < interval.start);
+        let last = self.intervals.partition_point(|i| i.start <= interval.end);
+        let pieces: Vec<Interval<T>> = self.intervals[first..last]
+            .iter()
+            .flat_map(|i| i.difference(&interval))
+            .collect();
+        self.intervals.splice(first..last, pieces);
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let i = self.intervals.partition_point(|i| i.end < value);
+        self.intervals.get(i).is_some_and(|i| i.contains(value))
+    }
+
+    /// How many values are in the set.
+    pub fn len(&self) -> u128 {
+        self.intervals.iter().map(Interval::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// The intervals in order, from the lowest values up.
+    pub fn iter(&self) -> impl Iterator<Item = &Interval<T>> {
+        self.intervals.iter()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        other.iter().for_each(|&i| union.insert(i));
+        union
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut difference = self.clone();
+        other.iter().for_each(|&i| difference.remove(i));
+        difference
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.difference(&self.difference(other))
+    }
+
+    /// Everything in `within` that isn't in the set.
+    pub fn gaps(&self, within: Interval<T>) -> Self {
+        let mut gaps = Self::new();
+        gaps.insert(within);
+        gaps.difference(self)
+    }
+}
+
+/// Split everything the intervals cover into stretches where the same
+/// number of them overlap, and count them. The stretches come in order
+/// and together run from the lowest start to the highest end, so gaps
+/// between the intervals show up with a count of 0.
+pub fn coverage<T: Discrete>(
+    intervals: impl IntoIterator<Item = Interval<T>>,
+) -> Vec<(Interval<T>, usize)> {
+    // Each interval starts counting at its start and stops just after
+    // its end.
+    let mut events: Vec<(T, isize)> = Vec::new();
+    for i in intervals {
+        events.push((i.start, 1));
+        if let Some(after) = i.end.succ() {
+            events.push((after, -1));
+        }
+    }
+    events.sort_unstable();
+
+    let mut stretches: Vec<(Interval<T>, usize)> = Vec::new();
+    let mut add = |stretch: Interval<T>, depth: usize| match stretches.last_mut() {
+        // An interval ending right where another starts doesn't change
+        // the count.
+        Some((last, count)) if *count == depth => last.end = stretch.end,
+        _ => stretches.push((stretch, depth)),
+    };
+    let mut depth = 0;
+    let mut from = None;
+    for (at, change) in events {
+        match from {
+            Some(from) if from < at => {
+                // `at` is bigger than something, so it has a `pred`.
+                add(
+                    Interval::new(from, at.pred().unwrap()).unwrap(),
+                    depth as usize,
+                );
+            }
+            _ => {}
+        }
+        depth += change;
+        from = Some(at);
+    }
+    if let Some(from) = from.filter(|_| depth > 0) {
+        add(Interval::new(from, T::MAX).unwrap(), depth as usize);
+    }
+    stretches
+}
+
+/// Intervals with values attached, set up so we can quickly find all
+/// the ones that overlap a point or another interval.
+///
+/// It's a balanced binary search tree on where the intervals start,
+/// laid out in a sorted array: the middle entry of any stretch is the
+/// root of the tree for that stretch. Each node also knows the highest
+/// end anywhere under it, so a query can skip any subtree that ends
+/// before the query starts. Queries take O(log n) plus however many
+/// intervals they find.
+#[derive(Debug, Clone)]
+pub struct IntervalTree<T, V> {
+    entries: Vec<(Interval<T>, V)>,
+    /// The highest end in the subtree rooted at each entry.
+    max_end: Vec<T>,
+}
+
+impl<T: Discrete, V> IntervalTree<T, V> {
+    pub fn new(mut entries: Vec<(Interval<T>, V)>) -> Self {
+        entries.sort_by_key(|(interval, _)| *interval);
+        let mut max_end: Vec<T> = entries.iter().map(|(interval, _)| interval.end).collect();
+        Self::fill_max_end(&mut max_end, 0, entries.len());
+        Self { entries, max_end }
+    }
+
+    /// Work out the highest end under the root of `lo..hi`, which
+    /// starts out holding its own end, and give it back.
+    fn fill_max_end(max_end: &mut [T], lo: usize, hi: usize) -> Option<T> {
+        if lo >= hi {
+            return None;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let left = Self::fill_max_end(max_end, lo, mid);
+        let right = Self::fill_max_end(max_end, mid + 1, hi);
+        max_end[mid] = [left, right]
+            .into_iter()
+            .flatten()
+            .fold(max_end[mid], T::max);
+        Some(max_end[mid])
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every entry whose interval overlaps `query`, in order of where
+    /// they start.
+    pub fn overlapping(&self, query: Interval<T>) -> Vec<&(Interval<T>, V)> {
+        let mut found = Vec::new();
+        self.search(query, 0, self.entries.len(), &mut found);
+        found
+    }
+
+    /// Every entry whose interval includes `point`.
+    pub fn stabbing(&self, point: T) -> Vec<&(Interval<T>, V)> {
+        self.overlapping(Interval::point(point))
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: Interval<T>,
+        lo: usize,
+        hi: usize,
+        found: &mut Vec<&'a (Interval<T>, V)>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_end[mid] < query.start {
+            return;
+        }
+        self.search(query, lo, mid, found);
+        let entry = &self.entries[mid];
+        // Everything from here on starts too late.
+        if entry.0.start > query.end {
+            return;
+        }
+        if entry.0.overlaps(&query) {
+            found.push(entry);
+        }
+        self.search(query, mid + 1, hi, found);
+    }
+}
+
+impl<T: Discrete, V> FromIterator<(Interval<T>, V)> for IntervalTree<T, V> {
+    fn from_iter<I: IntoIterator<Item = (Interval<T>, V)>>(entries: I) -> Self {
+        Self::new(entries.into_iter().collect())
+    }
+}
+
+impl<T: Discrete> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(intervals: I) -> Self {
+        let mut set = Self::new();
+        intervals.into_iter().for_each(|i| set.insert(i));
+        set
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for IntervalSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let intervals: Vec<String> = self.intervals.iter().map(|i| i.to_string()).collect();
+        write!(f, "{}", intervals.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    fn interval(start: u32, end: u32) -> Interval<u32> {
+        Interval::new(start, end).unwrap()
+    }
+
+    #[test]
+    fn test_interval() {
+        assert_eq!(None, Interval::new(5, 4));
+        assert_eq!(3, interval(2, 4).len());
+        assert_eq!(1, Interval::point(7u8).len());
+        assert_eq!(
+            u128::from(u64::MAX) + 1,
+            Interval::new(0, u64::MAX).unwrap().len()
+        );
+
+        assert!(interval(2, 8).contains_interval(&interval(3, 7)));
+        assert!(interval(5, 7).overlaps(&interval(7, 9)));
+        assert!(!interval(2, 3).overlaps(&interval(4, 5)));
+        assert!(interval(2, 3).is_adjacent(&interval(4, 5)));
+        assert!(!interval(2, 4).is_adjacent(&interval(4, 5)));
+        assert!(!interval(2, 3).is_adjacent(&interval(5, 5)));
+    }
+
+    #[test]
+    fn test_interval_algebra() {
+        assert_eq!(
+            Some(interval(4, 6)),
+            interval(2, 6).intersection(&interval(4, 8))
+        );
+        assert_eq!(None, interval(2, 4).intersection(&interval(6, 8)));
+
+        assert_eq!(Some(interval(2, 8)), interval(2, 6).union(&interval(4, 8)));
+        assert_eq!(Some(interval(2, 5)), interval(2, 3).union(&interval(4, 5)));
+        assert_eq!(None, interval(2, 4).union(&interval(6, 8)));
+
+        assert_eq!(
+            vec![interval(2, 3), interval(7, 8)],
+            interval(2, 8).difference(&interval(4, 6))
+        );
+        assert_eq!(
+            vec![interval(2, 3)],
+            interval(2, 6).difference(&interval(4, 8))
+        );
+        assert_eq!(
+            Vec::<Interval<u32>>::new(),
+            interval(3, 7).difference(&interval(2, 8))
+        );
+        assert_eq!(
+            vec![interval(2, 4)],
+            interval(2, 4).difference(&interval(6, 8))
+        );
+        assert_eq!(
+            vec![Interval::new(1u8, 255).unwrap()],
+            Interval::new(0u8, 255)
+                .unwrap()
+                .difference(&Interval::point(0))
+        );
+    }
+
+    #[test]
+    fn test_interval_set() {
+        let mut set: IntervalSet<u32> = [interval(2, 4), interval(6, 8), interval(12, 15)]
+            .into_iter()
+            .collect();
+        assert_eq!("2-4,6-8,12-15", set.to_string());
+        assert_eq!(10, set.len());
+
+        set.insert(interval(5, 5));
+        assert_eq!("2-8,12-15", set.to_string());
+        set.insert(interval(10, 10));
+        assert_eq!("2-8,10-10,12-15", set.to_string());
+        set.insert(interval(9, 11));
+        assert_eq!("2-15", set.to_string());
+
+        set.remove(interval(5, 6));
+        set.remove(interval(15, 20));
+        assert_eq!("2-4,7-14", set.to_string());
+        assert!(set.contains(3));
+        assert!(!set.contains(5));
+        assert!(!set.contains(15));
+
+        assert_eq!("0-1,5-6,15-20", set.gaps(interval(0, 20)).to_string());
+        let other: IntervalSet<u32> = [interval(4, 8)].into_iter().collect();
+        assert_eq!("4-4,7-8", set.intersection(&other).to_string());
+        assert_eq!("2-14", set.union(&other).to_string());
+    }
+
+    #[test]
+    fn test_coverage() {
+        assert_eq!(
+            vec![
+                (interval(2, 3), 1),
+                (interval(4, 4), 2),
+                (interval(5, 5), 0),
+                (interval(6, 7), 1),
+                (interval(8, 8), 2),
+                (interval(9, 9), 1),
+            ],
+            coverage([
+                interval(2, 4),
+                interval(4, 4),
+                interval(6, 8),
+                interval(8, 9)
+            ])
+        );
+        assert_eq!(
+            vec![(Interval::new(250u8, 255).unwrap(), 1)],
+            coverage([Interval::new(250u8, 255).unwrap()])
+        );
+        assert_eq!(Vec::<(Interval<u8>, usize)>::new(), coverage([]));
+    }
+
+    #[test]
+    fn test_interval_tree() {
+        let tree: IntervalTree<u32, char> = [
+            (interval(5, 20), 'a'),
+            (interval(10, 30), 'b'),
+            (interval(12, 15), 'c'),
+            (interval(17, 19), 'd'),
+            (interval(30, 40), 'e'),
+            (interval(1, 3), 'f'),
+        ]
+        .into_iter()
+        .collect();
+        let names = |found: Vec<&(Interval<u32>, char)>| -> String {
+            found.iter().map(|(_, name)| name).collect()
+        };
+
+        assert_eq!(6, tree.len());
+        assert_eq!("ab", names(tree.stabbing(16)));
+        assert_eq!("be", names(tree.stabbing(30)));
+        assert_eq!("", names(tree.stabbing(4)));
+        assert_eq!("fabc", names(tree.overlapping(interval(2, 12))));
+        assert_eq!("", names(tree.overlapping(interval(41, 50))));
+        assert!(IntervalTree::<u32, ()>::new(Vec::new())
+            .stabbing(1)
+            .is_empty());
+    }
+
+    fn set_of(intervals: &[(u8, u8)]) -> (IntervalSet<u8>, BTreeSet<u8>) {
+        let mut set = IntervalSet::new();
+        let mut values = BTreeSet::new();
+        for &(a, b) in intervals {
+            set.insert(Interval::new(a.min(b), a.max(b)).unwrap());
+            values.extend(a.min(b)..=a.max(b));
+        }
+        (set, values)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_interval_set_matches_btreeset(
+            a in prop::collection::vec((0..60u8, 0..60u8), 0..8),
+            b in prop::collection::vec((0..60u8, 0..60u8), 0..8),
+        ) {
+            let (a, a_values) = set_of(&a);
+            let (b, b_values) = set_of(&b);
+
+            // Merged all the way: no overlaps and no neighbors.
+            for pair in a.intervals.windows(2) {
+                prop_assert!(pair[0].end().succ().unwrap() < pair[1].start());
+            }
+            prop_assert_eq!(a_values.len() as u128, a.len());
+
+            let values = |set: &IntervalSet<u8>| {
+                set.iter().flat_map(|&i| RangeInclusive::from(i)).collect::<BTreeSet<u8>>()
+            };
+            prop_assert_eq!(&a_values | &b_values, values(&a.union(&b)));
+            prop_assert_eq!(&a_values & &b_values, values(&a.intersection(&b)));
+            prop_assert_eq!(&a_values - &b_values, values(&a.difference(&b)));
+        }
+
+        #[test]
+        fn prop_coverage_counts_every_value(
+            intervals in prop::collection::vec((0..60u8, 0..60u8), 1..8),
+        ) {
+            let intervals: Vec<Interval<u8>> = intervals
+                .iter()
+                .map(|&(a, b)| Interval::new(a.min(b), a.max(b)).unwrap())
+                .collect();
+            let stretches = coverage(intervals.iter().copied());
+            for pair in stretches.windows(2) {
+                prop_assert_eq!(pair[0].0.end().succ(), Some(pair[1].0.start()));
+                prop_assert!(pair[0].1 != pair[1].1);
+            }
+            for (stretch, count) in stretches {
+                for value in RangeInclusive::from(stretch) {
+                    prop_assert_eq!(count, intervals.iter().filter(|i| i.contains(value)).count());
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,18 @@
 use aoc_runner_derive::aoc_lib;
 
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
+pub mod error;
+pub mod interval;
+pub mod registry;
+pub mod verify;
+
+pub use registry::{find, solvers, Answer, Solver};
+
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
 mod day6;
+mod day7;
 
 aoc_lib! { year = 2022 }
@@ -0,0 +1,211 @@
+use crate::error::{Error, ParseError, SolveError};
+use std::fmt;
+
+/// What a solver comes up with. Most puzzles want a number, but some
+/// (like day 5) spell out their answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Integer(i64),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Integer(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(n: i32) -> Self {
+        Answer::Integer(n.into())
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(n: u32) -> Self {
+        Answer::Integer(n.into())
+    }
+}
+
+/// Anything too big for an `i64` gets spelled out instead.
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        i64::try_from(n).map_or_else(|_| Answer::Text(n.to_string()), Answer::Integer)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+/// Puzzle input that's been through a generator and is ready to be
+/// solved.
+pub trait Generated {
+    fn solve(&self) -> Result<Answer, SolveError>;
+}
+
+/// One way of solving one part of one day.
+///
+/// Solving happens in two steps, the same as with `cargo aoc`: the
+/// generator turns the input into something the solver can work on,
+/// and then the solver works on it. They can be run (and timed)
+/// separately with `generate` and `Generated::solve`, or together
+/// with `solve`.
+pub trait Solver: Sync {
+    fn day(&self) -> u8;
+    fn part(&self) -> u8;
+    fn name(&self) -> &'static str;
+    fn generate(&self, input: &str) -> Result<Box<dyn Generated>, ParseError>;
+
+    fn solve(&self, input: &str) -> Result<Answer, Error> {
+        Ok(self.generate(input)?.solve()?)
+    }
+}
+
+/// A generator and a solver for the days to register.
+pub(crate) struct Solution<T> {
+    pub day: u8,
+    pub part: u8,
+    pub name: &'static str,
+    pub generator: fn(&str) -> Result<T, ParseError>,
+    pub solver: fn(&T) -> Result<Answer, SolveError>,
+}
+
+struct Prepared<T> {
+    input: T,
+    solver: fn(&T) -> Result<Answer, SolveError>,
+}
+
+impl<T> Generated for Prepared<T> {
+    fn solve(&self) -> Result<Answer, SolveError> {
+        (self.solver)(&self.input)
+    }
+}
+
+impl<T: 'static> Solver for Solution<T> {
+    fn day(&self) -> u8 {
+        self.day
+    }
+
+    fn part(&self) -> u8 {
+        self.part
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn generate(&self, input: &str) -> Result<Box<dyn Generated>, ParseError> {
+        // cargo-aoc hands over its input without the trailing newlines,
+        // so the generators are written expecting that.
+        let input = (self.generator)(input.trim_end_matches('\n'))?;
+        Ok(Box::new(Prepared {
+            input,
+            solver: self.solver,
+        }))
+    }
+}
+
+static DAYS: &[&[&dyn Solver]] = &[
+    crate::day1::SOLVERS,
+    crate::day2::SOLVERS,
+    crate::day3::SOLVERS,
+    crate::day4::SOLVERS,
+    crate::day5::SOLVERS,
+    crate::day6::SOLVERS,
+    crate::day7::SOLVERS,
+];
+
+/// Every registered solver, in day and then part order. A day and part
+/// can have more than one solver; the first one is the main one.
+pub fn solvers() -> impl Iterator<Item = &'static dyn Solver> {
+    DAYS.iter().flat_map(|day| day.iter().copied())
+}
+
+/// Find the solver for `day` and `part`, picking the one called `name`
+/// if there's more than one.
+pub fn find(day: u8, part: u8, name: Option<&str>) -> Option<&'static dyn Solver> {
+    solvers().find(|solver| {
+        solver.day() == day
+            && solver.part() == part
+            && name.is_none_or(|name| solver.name() == name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_every_day_has_both_parts() {
+        for day in 1..=7 {
+            for part in 1..=2 {
+                assert!(find(day, part, None).is_some(), "day {} part {}", day, part);
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_by_name() {
+        let solver = find(5, 2, Some("find_tops_after_moves_9001")).unwrap();
+        assert_eq!((5, 2), (solver.day(), solver.part()));
+        assert!(find(5, 2, Some("find_tops_after_moves")).is_none());
+    }
+
+    #[test]
+    fn test_solve() {
+        let solver = find(1, 1, None).unwrap();
+        assert_eq!(
+            Answer::Integer(3000),
+            solver.solve("1000\n2000\n\n3000\n").unwrap()
+        );
+
+        let solver = find(5, 1, None).unwrap();
+        assert_eq!(
+            Answer::Text(String::from("BA")),
+            solver
+                .solve("[A]\n[B] [C]\n 1   2 \n\nmove 1 from 1 to 2\n")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_answer_from_usize() {
+        assert_eq!(Answer::Integer(7), Answer::from(7usize));
+        assert_eq!(
+            Answer::Text(usize::MAX.to_string()),
+            Answer::from(usize::MAX)
+        );
+    }
+
+    #[test]
+    fn test_solve_error() {
+        let err = find(4, 1, None).unwrap().solve("1-2,3").unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Parse(ParseError {
+                day: 4,
+                line: 1,
+                ..
+            })
+        ));
+
+        let err = find(6, 1, None).unwrap().solve("abcabc").unwrap_err();
+        assert!(matches!(err, Error::Solve(SolveError { day: 6, .. })));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_solvers_never_panic(input in "[ -~\n]{0,200}|\\PC{0,50}") {
+            for solver in solvers() {
+                let _ = solver.solve(&input);
+            }
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use crate::error::Error;
+use crate::registry::{solvers, Answer, Solver};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The answers we expect to get for each day and part, read from a
+/// TOML file like this:
+///
+/// ```toml
+/// [day1]
+/// part1 = 24000
+/// part2 = 45000
+///
+/// [day5]
+/// part1 = "CMZ"
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct Answers(HashMap<(u8, u8), Answer>);
+
+#[derive(Debug)]
+pub enum AnswersError {
+    Toml(toml::de::Error),
+    /// A key that isn't `dayN` or `partN`, or an answer that isn't an
+    /// integer or a string.
+    Invalid {
+        key: String,
+    },
+}
+
+impl fmt::Display for AnswersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnswersError::Toml(e) => write!(f, "{}", e),
+            AnswersError::Invalid { key } => write!(f, "don't know what to do with `{}`", key),
+        }
+    }
+}
+
+impl std::error::Error for AnswersError {}
+
+fn numbered(key: &str, prefix: &str) -> Option<u8> {
+    key.strip_prefix(prefix)?.parse().ok()
+}
+
+impl Answers {
+    pub fn parse(input: &str) -> Result<Self, AnswersError> {
+        let table = input.parse::<toml::Table>().map_err(AnswersError::Toml)?;
+        let mut answers = HashMap::new();
+
+        for (day_key, parts) in table {
+            let day = numbered(&day_key, "day");
+            let parts = parts.as_table().zip(day);
+            let (parts, day) = parts.ok_or(AnswersError::Invalid {
+                key: day_key.clone(),
+            })?;
+            for (part_key, value) in parts {
+                let invalid = || AnswersError::Invalid {
+                    key: format!("{}.{}", day_key, part_key),
+                };
+                let part = numbered(part_key, "part").ok_or_else(invalid)?;
+                let answer = match value {
+                    toml::Value::Integer(n) => Answer::Integer(*n),
+                    toml::Value::String(s) => Answer::Text(s.clone()),
+                    _ => return Err(invalid()),
+                };
+                answers.insert((day, part), answer);
+            }
+        }
+
+        Ok(Self(answers))
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&Answer> {
+        self.0.get(&(day, part))
+    }
+}
+
+/// How a solver did against its expected answer.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Pass,
+    Fail {
+        expected: Answer,
+        actual: Answer,
+    },
+    /// There's no input file for the day.
+    MissingInput,
+    /// The solver ran, but there's nothing to check its answer against.
+    MissingAnswer {
+        actual: Answer,
+    },
+    Error(Error),
+}
+
+impl Outcome {
+    pub fn is_pass(&self) -> bool {
+        matches!(self, Outcome::Pass)
+    }
+
+    /// Whether the solver got something wrong. Missing an input or an
+    /// answer doesn't count, since there's nothing to be wrong about.
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Outcome::Fail { .. } | Outcome::Error(_))
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Pass => write!(f, "pass"),
+            Outcome::Fail { expected, actual } => {
+                write!(f, "FAIL\n  - {}\n  + {}", expected, actual)
+            }
+            Outcome::MissingInput => write!(f, "missing input"),
+            Outcome::MissingAnswer { actual } => write!(f, "missing answer (got {})", actual),
+            Outcome::Error(e) => write!(f, "ERROR {}", e),
+        }
+    }
+}
+
+pub struct Report {
+    pub solver: &'static dyn Solver,
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {} part {} ({}): {}",
+            self.solver.day(),
+            self.solver.part(),
+            self.solver.name(),
+            self.outcome
+        )
+    }
+}
+
+/// Run one solver on `input` (if there is any) and check what it comes
+/// up with.
+pub fn verify(solver: &dyn Solver, answers: &Answers, input: Option<&str>) -> Outcome {
+    let actual = match input.map(|input| solver.solve(input)) {
+        None => return Outcome::MissingInput,
+        Some(Err(e)) => return Outcome::Error(e),
+        Some(Ok(actual)) => actual,
+    };
+    match answers.get(solver.day(), solver.part()) {
+        None => Outcome::MissingAnswer { actual },
+        Some(expected) if expected == &actual => Outcome::Pass,
+        Some(expected) => Outcome::Fail {
+            expected: expected.clone(),
+            actual,
+        },
+    }
+}
+
+/// Run every registered solver, getting each day's input from `input`.
+pub fn verify_all(answers: &Answers, input: impl Fn(u8) -> Option<String>) -> Vec<Report> {
+    let mut inputs = HashMap::new();
+    solvers()
+        .map(|solver| {
+            let day_input = inputs
+                .entry(solver.day())
+                .or_insert_with(|| input(solver.day()));
+            Report {
+                solver,
+                outcome: verify(solver, answers, day_input.as_deref()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSWERS: &str = "[day1]
+part1 = 11111
+part2 = 1
+
+[day5]
+part1 = \"CMZ\"
+";
+
+    #[test]
+    fn test_parse_answers() {
+        let answers = Answers::parse(ANSWERS).unwrap();
+        assert_eq!(Some(&Answer::Integer(11111)), answers.get(1, 1));
+        assert_eq!(Some(&Answer::Text(String::from("CMZ"))), answers.get(5, 1));
+        assert_eq!(None, answers.get(5, 2));
+    }
+
+    #[test]
+    fn test_parse_answers_errors() {
+        assert!(matches!(
+            Answers::parse("[day1]\npart1 = 1.5"),
+            Err(AnswersError::Invalid { key }) if key == "day1.part1"
+        ));
+        assert!(matches!(
+            Answers::parse("[one]\npart1 = 1"),
+            Err(AnswersError::Invalid { key }) if key == "one"
+        ));
+        assert!(matches!(
+            Answers::parse("[day1"),
+            Err(AnswersError::Toml(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_all() {
+        let answers = Answers::parse(ANSWERS).unwrap();
+        let reports = verify_all(&answers, |day| match day {
+            1 => Some(String::from("10000\n1000\n100\n10\n1\n")),
+            4 => Some(String::from("2-4,6")),
+            _ => None,
+        });
+        let outcome = |day, part| {
+            &reports
+                .iter()
+                .find(|r| (r.solver.day(), r.solver.part()) == (day, part))
+                .unwrap()
+                .outcome
+        };
+
+        assert_eq!(&Outcome::Pass, outcome(1, 1));
+        assert_eq!(
+            &Outcome::Fail {
+                expected: Answer::Integer(1),
+                actual: Answer::Integer(11111)
+            },
+            outcome(1, 2)
+        );
+        assert!(matches!(outcome(4, 1), Outcome::Error(_)));
+        assert_eq!(&Outcome::MissingInput, outcome(5, 1));
+        assert!(outcome(1, 2).is_failure());
+        assert!(outcome(4, 1).is_failure());
+        assert!(!outcome(5, 1).is_failure());
+    }
+}